@@ -0,0 +1,132 @@
+//! Move-by-move review of a finished game.
+//!
+//! Every position is evaluated by [`MonteCarlo`] rollouts: the value of a
+//! move is the average final score of random games after it. A move is
+//! graded by how much of the best move's expected gain it gives up.
+
+use crate::game::{Direction, Game, Turn};
+use crate::montecarlo::MonteCarlo;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quality {
+    Best,
+    Good,
+    Inaccuracy,
+    Blunder,
+}
+
+impl Quality {
+    /// Grades a move giving up `loss`, a share of the points the best move
+    /// was expected to gain.
+    pub fn of_loss(loss: f64) -> Self {
+        if loss <= 0.0 {
+            Quality::Best
+        } else if loss < 0.1 {
+            Quality::Good
+        } else if loss < 0.25 {
+            Quality::Inaccuracy
+        } else {
+            Quality::Blunder
+        }
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            Quality::Best => "Best".to_string(),
+            Quality::Good => "Good".to_string(),
+            Quality::Inaccuracy => "Inaccuracy".to_string(),
+            Quality::Blunder => "Blunder".to_string(),
+        }
+    }
+}
+
+/// The verdict on one move.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Review {
+    pub played: Direction,
+    /// The move the engine prefers.
+    pub best: Direction,
+    /// Expected final score after each legal move, in direction order.
+    pub values: Vec<(Direction, f64)>,
+    /// Share of the best move's expected gain lost by the move played.
+    pub loss: f64,
+    pub quality: Quality,
+}
+
+/// The engine used for reviews, quicker per move than the one used for play.
+pub fn engine() -> MonteCarlo {
+    MonteCarlo::new(100, Some(Duration::from_millis(50)), 0)
+}
+
+/// Reviews the move played at `turn` of `game`.
+pub fn review(game: &Game, turn: &Turn, engine: &mut MonteCarlo) -> Review {
+    let mut position = game.playout(0);
+    position.board = turn.board.clone();
+    position.score = turn.score;
+    position.moves = turn.moves;
+    position.game_over = false;
+    let values = evaluate(&position, engine);
+
+    let (best, best_value) = values
+        .iter()
+        .rev()
+        .copied()
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .unwrap_or((turn.direction, 0.0));
+    let played_value = values
+        .iter()
+        .find(|(direction, _)| *direction == turn.direction)
+        .map_or(best_value, |&(_, value)| value);
+    let gain = best_value - f64::from(turn.score);
+    let loss = if best == turn.direction || gain <= 0.0 {
+        0.0
+    } else {
+        (best_value - played_value) / gain
+    };
+    Review {
+        played: turn.direction,
+        best,
+        values,
+        loss,
+        quality: Quality::of_loss(loss),
+    }
+}
+
+/// Expected final score after each legal move from `position`, in direction
+/// order. A lone legal move isn't evaluated, and gets a value of 0.
+pub fn evaluate(position: &Game, engine: &mut MonteCarlo) -> Vec<(Direction, f64)> {
+    let legal: Vec<Direction> = position
+        .board
+        .shape()
+        .directions()
+        .iter()
+        .copied()
+        .filter(|&direction| position.slide(direction).0 != position.board)
+        .collect();
+    // The only legal move is as good as it gets, so skip the rollouts
+    if legal.len() == 1 {
+        return vec![(legal[0], 0.0)];
+    }
+    let averages = engine.evaluate(position, &legal);
+    legal.into_iter().zip(averages).collect()
+}
+
+/// Reviews every move of `game` in order. `progress` sees each review as
+/// it's made; returning `false` stops the analysis early.
+pub fn analyze(
+    game: &Game,
+    engine: &mut MonteCarlo,
+    mut progress: impl FnMut(&Review) -> bool,
+) -> Vec<Review> {
+    let mut reviews = Vec::new();
+    for turn in game.turns() {
+        let review = review(game, &turn, engine);
+        let keep_going = progress(&review);
+        reviews.push(review);
+        if !keep_going {
+            break;
+        }
+    }
+    reviews
+}
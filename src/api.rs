@@ -0,0 +1,128 @@
+//! A local HTTP/JSON API for driving the game from scripts.
+//!
+//! | Request             | Body                        | Effect             |
+//! |---------------------|-----------------------------|--------------------|
+//! | `GET /state`        |                             | none               |
+//! | `POST /move`        | `{"direction": "left"}`     | moves the tiles    |
+//! | `POST /undo`        |                             | undoes a move      |
+//! | `POST /new`         |                             | starts a new game  |
+//!
+//! Directions are `up`, `down`, `left` and `right`, plus `up_left`,
+//! `up_right`, `down_left` and `down_right` on hex boards. Every request is
+//! answered with the resulting [`PlayerState`] as JSON, or with
+//! `{"error": "..."}` and a 4xx status.
+//!
+//! The server only parses requests; each one is handed to the game as a
+//! [`Request`], which the game answers once it has applied the command.
+
+use crate::http::{read_request, respond};
+use crate::protocol::PlayerState;
+use serde::Deserialize;
+use std::io;
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+
+/// Address used when the API is enabled without one.
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:8049";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    State,
+    Move(String),
+    Undo,
+    NewGame,
+}
+
+/// What the game sends back: its new state, or why the command failed.
+pub type Response = Result<PlayerState, String>;
+
+/// A command waiting for the game to apply it.
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub command: Command,
+    reply: Arc<Mutex<Option<mpsc::Sender<Response>>>>,
+}
+
+impl Request {
+    /// Answers the client. Only the first reply is sent.
+    pub fn respond(&self, result: Response) {
+        if let Some(reply) = self.reply.lock().unwrap().take() {
+            let _ = reply.send(result);
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct MoveBody {
+    direction: String,
+}
+
+/// How long to wait after a failed accept before the next.
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Accepts clients on `listener`, passing their commands to `requests`. A
+/// client that can't be accepted is logged and skipped, so the game stays
+/// reachable.
+pub fn serve(listener: TcpListener, requests: UnboundedSender<Request>) -> io::Result<()> {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(error) => {
+                eprintln!("Couldn't accept an API client: {error}");
+                // Out of file descriptors, say; give some time to close
+                thread::sleep(ACCEPT_RETRY_DELAY);
+                continue;
+            }
+        };
+        let requests = requests.clone();
+        thread::spawn(move || {
+            let _ = handle_connection(stream, &requests);
+        });
+    }
+    Ok(())
+}
+
+fn handle_connection(stream: TcpStream, requests: &UnboundedSender<Request>) -> io::Result<()> {
+    let request = read_request(&stream)?;
+    let command = match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/state") => Command::State,
+        ("POST", "/move") => match serde_json::from_str::<MoveBody>(&request.body) {
+            Ok(body) => Command::Move(body.direction),
+            Err(error) => {
+                return respond_error(&stream, "400 Bad Request", &error.to_string());
+            }
+        },
+        ("POST", "/undo") => Command::Undo,
+        ("POST", "/new") => Command::NewGame,
+        (_, "/state" | "/move" | "/undo" | "/new") => {
+            return respond_error(&stream, "405 Method Not Allowed", "method not allowed");
+        }
+        _ => return respond_error(&stream, "404 Not Found", "not found"),
+    };
+
+    let (reply, result) = mpsc::channel();
+    let request = Request {
+        command,
+        reply: Arc::new(Mutex::new(Some(reply))),
+    };
+    if requests.send(request).is_err() {
+        return respond_error(&stream, "503 Service Unavailable", "the game has closed");
+    }
+    match result.recv() {
+        Ok(Ok(state)) => {
+            let body = serde_json::to_string(&state).unwrap_or_default();
+            respond(&stream, "200 OK", "application/json", &body)
+        }
+        Ok(Err(message)) => respond_error(&stream, "400 Bad Request", &message),
+        Err(_) => respond_error(&stream, "503 Service Unavailable", "the game has closed"),
+    }
+}
+
+fn respond_error(stream: &TcpStream, status: &str, message: &str) -> io::Result<()> {
+    let body = serde_json::json!({ "error": message }).to_string();
+    respond(stream, status, "application/json", &body)
+}
@@ -0,0 +1,147 @@
+//! Hints and autoplay from one of the library's strategies.
+
+use crate::Message;
+use iced::Task;
+use iced::futures::channel::oneshot;
+use iced_2048::bitboard::Bitboard;
+use iced_2048::board::Board;
+use iced_2048::data_file_path;
+use iced_2048::game::{Direction, Game, Mode};
+use iced_2048::montecarlo::MonteCarlo;
+use iced_2048::ntuple::NTupleNetwork;
+use iced_2048::strategy::{Greedy, Strategy};
+use std::time::Duration;
+
+/// Keeps Monte Carlo moves quick enough for autoplay.
+const MONTE_CARLO_BUDGET: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Engine {
+    #[default]
+    Greedy,
+    NTuple,
+    MonteCarlo,
+}
+
+pub const ENGINES: [Engine; 3] = [Engine::Greedy, Engine::NTuple, Engine::MonteCarlo];
+
+impl Engine {
+    pub fn label(self) -> String {
+        match self {
+            Engine::Greedy => "Greedy".to_string(),
+            Engine::NTuple => "N-tuple".to_string(),
+            Engine::MonteCarlo => "Monte Carlo".to_string(),
+        }
+    }
+}
+
+pub struct Assist {
+    pub engine: Engine,
+    pub autoplay: bool,
+    /// The last hint, or why the engine couldn't give one.
+    pub hint: Option<String>,
+    /// Loaded the first time the n-tuple engine is used.
+    ntuple: Option<NTupleNetwork>,
+    monte_carlo: MonteCarlo,
+}
+
+impl Default for Assist {
+    fn default() -> Self {
+        Assist {
+            engine: Engine::default(),
+            autoplay: false,
+            hint: None,
+            ntuple: None,
+            monte_carlo: MonteCarlo::new(200, Some(MONTE_CARLO_BUDGET), 0),
+        }
+    }
+}
+
+impl Assist {
+    pub fn set_engine(&mut self, engine: Engine) {
+        self.engine = engine;
+        self.hint = None;
+    }
+
+    /// The engine's move for `game`, or a message saying why there's none.
+    pub fn choose(&mut self, game: &Game) -> Result<Direction, String> {
+        playable(game)?;
+        let strategy: &mut dyn Strategy = match self.engine {
+            Engine::Greedy => &mut Greedy,
+            Engine::NTuple => {
+                if Bitboard::from_game(game).is_none() {
+                    return Err("The n-tuple engine only plays the standard 4×4 game".to_string());
+                }
+                self.load_ntuple()?
+            }
+            Engine::MonteCarlo => &mut self.monte_carlo,
+        };
+        strategy.choose(game).ok_or_else(no_move)
+    }
+
+    /// Shows the engine's move for `game`. Monte Carlo thinks for up to its
+    /// time budget, so it does so off the UI, and its hint comes back as a
+    /// [`Message::HintReady`].
+    pub fn hint(&mut self, game: &Game) -> Task<Message> {
+        if self.engine != Engine::MonteCarlo || playable(game).is_err() {
+            let hint = self.choose(game);
+            self.show_hint(hint);
+            return Task::none();
+        }
+        self.hint = Some(format!("{} is thinking…", self.engine.label()));
+        let mut engine = self.monte_carlo.clone();
+        let thinking = game.clone();
+        let (sender, receiver) = oneshot::channel();
+        std::thread::spawn(move || {
+            let _ = sender.send(engine.choose(&thinking).ok_or_else(no_move));
+        });
+        let board = game.board.clone();
+        Task::perform(receiver, move |result| {
+            Message::HintReady(board.clone(), result.unwrap_or_else(|_| Err(no_move())))
+        })
+    }
+
+    /// Shows a hint worked out on `board`, unless the game has moved on.
+    pub fn hint_ready(&mut self, game: &Game, board: &Board, hint: Result<Direction, String>) {
+        if game.board == *board && self.hint.is_some() {
+            self.show_hint(hint);
+        }
+    }
+
+    fn show_hint(&mut self, hint: Result<Direction, String>) {
+        self.hint = Some(match hint {
+            Ok(direction) => format!("{} suggests {}", self.engine.label(), direction.label()),
+            Err(message) => message,
+        });
+    }
+
+    fn load_ntuple(&mut self) -> Result<&mut NTupleNetwork, String> {
+        if self.ntuple.is_none() {
+            let path = data_file_path("ntuple.bin")
+                .ok_or_else(|| "No data directory for the n-tuple weights".to_string())?;
+            let network = NTupleNetwork::load(&path).map_err(|error| {
+                format!(
+                    "Couldn't load n-tuple weights from {} ({error}); train them with iced-2048-train",
+                    path.display()
+                )
+            })?;
+            self.ntuple = Some(network);
+        }
+        Ok(self.ntuple.as_mut().unwrap())
+    }
+}
+
+/// Why no engine can play `game`, if that's the case.
+fn playable(game: &Game) -> Result<(), String> {
+    if game.mode != Mode::Classic {
+        return Err("Hints and autoplay are only available in classic games".to_string());
+    }
+    if game.game_over {
+        return Err("The game is over".to_string());
+    }
+    Ok(())
+}
+
+fn no_move() -> String {
+    "No move changes the board".to_string()
+}
@@ -0,0 +1,14 @@
+//! Relay server for networked races. Listens on the address given as the
+//! first argument, or on the protocol's default address.
+
+use iced_2048::protocol::DEFAULT_ADDRESS;
+use std::net::TcpListener;
+
+fn main() -> std::io::Result<()> {
+    let address = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| DEFAULT_ADDRESS.to_string());
+    let listener = TcpListener::bind(&address)?;
+    println!("Relay listening on {}", listener.local_addr()?);
+    iced_2048::relay::serve(listener)
+}
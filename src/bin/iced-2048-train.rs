@@ -0,0 +1,153 @@
+//! Trains the n-tuple agent without a window.
+//!
+//! ```text
+//! iced-2048-train [--games N] [--seed S] [--alpha A] [--lambda L]
+//!                 [--tuples small|large] [--weights PATH]
+//!                 [--checkpoint-every N] [--resume]
+//! ```
+//!
+//! Training runs until the network has played `--games` games in total, saving
+//! a checkpoint every `--checkpoint-every` games. With `--resume`, training
+//! continues from the weight file, including its seed, learning rate and λ,
+//! and ends up with the same weights as an uninterrupted run.
+
+use iced_2048::ntuple::{LARGE_TUPLES, NTupleNetwork, SMALL_TUPLES};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+struct Options {
+    games: u64,
+    seed: u64,
+    alpha: f32,
+    lambda: f32,
+    tuples: &'static [&'static [u8]],
+    weights: Option<PathBuf>,
+    checkpoint_every: u64,
+    resume: bool,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options {
+        games: 100_000,
+        seed: 1,
+        alpha: 0.1,
+        lambda: 0.0,
+        tuples: SMALL_TUPLES,
+        weights: None,
+        checkpoint_every: 1_000,
+        resume: false,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{arg} needs a value"));
+        match arg.as_str() {
+            "--games" => options.games = parse(&arg, &value()?)?,
+            "--seed" => options.seed = parse(&arg, &value()?)?,
+            "--alpha" => options.alpha = parse(&arg, &value()?)?,
+            "--lambda" => options.lambda = parse(&arg, &value()?)?,
+            "--tuples" => {
+                options.tuples = match value()?.as_str() {
+                    "small" => SMALL_TUPLES,
+                    "large" => LARGE_TUPLES,
+                    other => return Err(format!("unknown tuple set {other}")),
+                }
+            }
+            "--weights" => options.weights = Some(PathBuf::from(value()?)),
+            "--checkpoint-every" => options.checkpoint_every = parse(&arg, &value()?)?,
+            "--resume" => options.resume = true,
+            "--help" | "-h" => {
+                return Err("usage: iced-2048-train [--games N] [--seed S] [--alpha A] \
+                     [--lambda L] [--tuples small|large] [--weights PATH] \
+                     [--checkpoint-every N] [--resume]"
+                    .to_string());
+            }
+            other => return Err(format!("unknown option {other}")),
+        }
+    }
+    if options.alpha.is_nan() || options.alpha <= 0.0 {
+        return Err(format!("--alpha must be above 0, not {}", options.alpha));
+    }
+    if !(0.0..=1.0).contains(&options.lambda) {
+        return Err(format!(
+            "--lambda must be from 0 to 1, not {}",
+            options.lambda
+        ));
+    }
+    Ok(options)
+}
+
+fn parse<T: std::str::FromStr>(option: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for {option}: {value}"))
+}
+
+fn main() -> ExitCode {
+    let options = match parse_options() {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{message}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let Some(path) = options
+        .weights
+        .or_else(|| iced_2048::data_file_path("ntuple.bin"))
+    else {
+        eprintln!("No data directory found; pass --weights");
+        return ExitCode::FAILURE;
+    };
+
+    let mut network = if options.resume && path.exists() {
+        match NTupleNetwork::load(&path) {
+            Ok(network) => {
+                println!(
+                    "Resuming from {} after {} games",
+                    path.display(),
+                    network.games_trained()
+                );
+                network
+            }
+            Err(error) => {
+                eprintln!("Couldn't load {}: {error}", path.display());
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        NTupleNetwork::new(options.tuples, options.alpha, options.lambda, options.seed)
+    };
+
+    let checkpoint_every = options.checkpoint_every.max(1);
+    let (mut total_score, mut wins, mut played) = (0u64, 0u64, 0u64);
+    let mut saved = false;
+    while network.games_trained() < options.games {
+        let stats = network.train_game();
+        total_score += u64::from(stats.score);
+        wins += u64::from(stats.max_tile >= 2048);
+        played += 1;
+
+        if network.games_trained().is_multiple_of(checkpoint_every)
+            || network.games_trained() == options.games
+        {
+            println!(
+                "{} games: average score {:.0}, reached 2048 in {:.1}%",
+                network.games_trained(),
+                total_score as f64 / played as f64,
+                100.0 * wins as f64 / played as f64
+            );
+            (total_score, wins, played) = (0, 0, 0);
+            if let Err(error) = network.save(&path) {
+                eprintln!("Couldn't save {}: {error}", path.display());
+                return ExitCode::FAILURE;
+            }
+            saved = true;
+        }
+    }
+    if saved {
+        println!("Weights saved to {}", path.display());
+    } else {
+        // A resumed run that had already finished trains nothing
+        println!("Already trained {} games", network.games_trained());
+    }
+    ExitCode::SUCCESS
+}
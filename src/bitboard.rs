@@ -0,0 +1,250 @@
+//! The standard 4×4 game packed into a `u64`, for search and training where
+//! [`Game`] would be too slow.
+//!
+//! Each cell holds a 4-bit rank in row-major order, cell 0 in the lowest
+//! bits: 0 is empty and rank `r` is the tile `2^r`. Ranks stop at 15, so two
+//! 32768 tiles don't merge.
+
+use crate::board::Shape;
+use crate::game::{Cell, Direction, Game};
+use crate::rng::SpawnRng;
+use crate::rules::MergeRule;
+use std::sync::OnceLock;
+
+/// The moves of the standard game, in the order strategies try them.
+pub const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+
+const MAX_RANK: u8 = 15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Bitboard(pub u64);
+
+/// Every possible row slid left and right, indexed by the row's 16 bits.
+struct RowTables {
+    left: Vec<u16>,
+    right: Vec<u16>,
+    left_score: Vec<u32>,
+    right_score: Vec<u32>,
+}
+
+fn row_tables() -> &'static RowTables {
+    static TABLES: OnceLock<RowTables> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let mut tables = RowTables {
+            left: vec![0; 1 << 16],
+            right: vec![0; 1 << 16],
+            left_score: vec![0; 1 << 16],
+            right_score: vec![0; 1 << 16],
+        };
+        for row in 0..=u16::MAX {
+            let (left, score) = slide_row_left(row);
+            tables.left[row as usize] = left;
+            tables.left_score[row as usize] = score;
+            let (right, score) = slide_row_left(reverse_row(row));
+            tables.right[row as usize] = reverse_row(right);
+            tables.right_score[row as usize] = score;
+        }
+        tables
+    })
+}
+
+/// Slides a row towards its lowest nibble, returning the row and the
+/// points scored.
+fn slide_row_left(row: u16) -> (u16, u32) {
+    let ranks: Vec<u8> = (0..4)
+        .map(|i| ((row >> (4 * i)) & 0xF) as u8)
+        .filter(|&rank| rank != 0)
+        .collect();
+    let mut merged = Vec::with_capacity(4);
+    let mut score = 0;
+    let mut i = 0;
+    while i < ranks.len() {
+        if i + 1 < ranks.len() && ranks[i] == ranks[i + 1] && ranks[i] < MAX_RANK {
+            merged.push(ranks[i] + 1);
+            score += 1 << (ranks[i] + 1);
+            i += 2;
+        } else {
+            merged.push(ranks[i]);
+            i += 1;
+        }
+    }
+    let row = merged
+        .iter()
+        .enumerate()
+        .fold(0, |row, (i, &rank)| row | (u16::from(rank) << (4 * i)));
+    (row, score)
+}
+
+fn reverse_row(row: u16) -> u16 {
+    (row >> 12) | ((row >> 4) & 0x00F0) | ((row << 4) & 0x0F00) | (row << 12)
+}
+
+/// Swaps rows and columns.
+fn transpose(x: u64) -> u64 {
+    let a1 = x & 0xF0F0_0F0F_F0F0_0F0F;
+    let a2 = x & 0x0000_F0F0_0000_F0F0;
+    let a3 = x & 0x0F0F_0000_0F0F_0000;
+    let a = a1 | (a2 << 12) | (a3 >> 12);
+    let b1 = a & 0xFF00_FF00_00FF_00FF;
+    let b2 = a & 0x00FF_00FF_0000_0000;
+    let b3 = a & 0x0000_0000_FF00_FF00;
+    b1 | (b2 >> 24) | (b3 << 24)
+}
+
+impl Bitboard {
+    /// The board of a standard 4×4 game, or `None` for other shapes, rules
+    /// or boards with blocks.
+    pub fn from_game(game: &Game) -> Option<Self> {
+        if game.board.shape() != Shape::Square(4) || game.variant.rule != MergeRule::Standard {
+            return None;
+        }
+        let mut board = Bitboard(0);
+        for (i, &cell) in game.board.cells().iter().enumerate() {
+            match cell {
+                Cell::Empty => {}
+                Cell::Tile(value) if value.is_power_of_two() && value > 1 => {
+                    board.set(i, value.ilog2().min(u32::from(MAX_RANK)) as u8);
+                }
+                Cell::Tile(_) | Cell::Block => return None,
+            }
+        }
+        Some(board)
+    }
+
+    pub fn rank(self, cell: usize) -> u8 {
+        ((self.0 >> (4 * cell)) & 0xF) as u8
+    }
+
+    pub fn set(&mut self, cell: usize, rank: u8) {
+        self.0 = (self.0 & !(0xF << (4 * cell))) | (u64::from(rank) << (4 * cell));
+    }
+
+    pub fn max_tile(self) -> u32 {
+        (0..16)
+            .map(|cell| self.rank(cell))
+            .max()
+            .filter(|&rank| rank > 0)
+            .map_or(0, |rank| 1 << rank)
+    }
+
+    pub fn empty_cells(self) -> usize {
+        (0..16).filter(|&cell| self.rank(cell) == 0).count()
+    }
+
+    /// The board after sliding in `direction`, before any tile spawns, and
+    /// the points scored. Diagonals leave the board as it is.
+    pub fn slide(self, direction: Direction) -> (Bitboard, u32) {
+        let tables = row_tables();
+        let slide_rows = |board: u64, rows: &[u16], scores: &[u32]| {
+            (0..4).fold((0u64, 0u32), |(result, score), r| {
+                let row = ((board >> (16 * r)) & 0xFFFF) as usize;
+                (
+                    result | (u64::from(rows[row]) << (16 * r)),
+                    score + scores[row],
+                )
+            })
+        };
+        let (board, score) = match direction {
+            Direction::Left => slide_rows(self.0, &tables.left, &tables.left_score),
+            Direction::Right => slide_rows(self.0, &tables.right, &tables.right_score),
+            Direction::Up => {
+                let (board, score) =
+                    slide_rows(transpose(self.0), &tables.left, &tables.left_score);
+                (transpose(board), score)
+            }
+            Direction::Down => {
+                let (board, score) =
+                    slide_rows(transpose(self.0), &tables.right, &tables.right_score);
+                (transpose(board), score)
+            }
+            _ => (self.0, 0),
+        };
+        (Bitboard(board), score)
+    }
+
+    /// Places a 2 or, one time in ten, a 4 on a random empty cell, with the
+    /// same odds as the game.
+    pub fn spawn(&mut self, rng: &mut SpawnRng) {
+        let empty = self.empty_cells();
+        if empty == 0 {
+            return;
+        }
+        let target = rng.below(empty);
+        let cell = (0..16)
+            .filter(|&cell| self.rank(cell) == 0)
+            .nth(target)
+            .expect("target is below the number of empty cells");
+        self.set(cell, if rng.chance(0.9) { 1 } else { 2 });
+    }
+
+    /// Whether no move changes the board.
+    pub fn is_over(self) -> bool {
+        DIRECTIONS
+            .iter()
+            .all(|&direction| self.slide(direction).0 == self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::game::Variant;
+
+    /// A random board with tiles up to 2048, about a third of it empty.
+    fn random_board(rng: &mut SpawnRng) -> Bitboard {
+        let mut board = Bitboard(0);
+        for cell in 0..16 {
+            if !rng.chance(0.3) {
+                board.set(cell, 1 + rng.below(11) as u8);
+            }
+        }
+        board
+    }
+
+    fn to_board(bitboard: Bitboard) -> Board {
+        let mut board = Board::new(Shape::Square(4));
+        for cell in 0..16 {
+            if let rank @ 1.. = bitboard.rank(cell) {
+                board[cell] = Cell::Tile(1 << rank);
+            }
+        }
+        board
+    }
+
+    #[test]
+    fn slides_like_the_game() {
+        let mut rng = SpawnRng::new(2048);
+        let mut game = Game::new_seeded(0, Variant::default(), false);
+        for _ in 0..2000 {
+            let bitboard = random_board(&mut rng);
+            game.board = to_board(bitboard);
+            assert_eq!(Bitboard::from_game(&game), Some(bitboard));
+            for direction in DIRECTIONS {
+                let (board, merges) = game.slide(direction);
+                let (slid, score) = bitboard.slide(direction);
+                assert_eq!(
+                    to_board(slid),
+                    board,
+                    "{direction:?} on {:#018x}",
+                    bitboard.0
+                );
+                assert_eq!(score, merges.iter().sum::<u32>());
+            }
+        }
+    }
+
+    #[test]
+    fn is_over_only_when_no_move_changes_the_board() {
+        let stuck = Bitboard(0x1212_2121_1212_2121);
+        assert!(stuck.is_over());
+        let mut open = stuck;
+        open.set(0, 2);
+        assert!(!open.is_over());
+    }
+}
@@ -0,0 +1,260 @@
+use crate::game::{Cell, Direction};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::{Index, IndexMut};
+use std::sync::{OnceLock, RwLock};
+
+/// The layout of the board's cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Shape {
+    /// A grid of `n` by `n` squares.
+    Square(usize),
+    /// A hexagon of flat-topped hex cells, `radius` cells out from the centre.
+    Hex(usize),
+}
+
+impl Default for Shape {
+    fn default() -> Self {
+        Shape::Square(4)
+    }
+}
+
+const SQUARE_DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+
+const HEX_DIRECTIONS: [Direction; 6] = [
+    Direction::Up,
+    Direction::UpRight,
+    Direction::DownRight,
+    Direction::Down,
+    Direction::DownLeft,
+    Direction::UpLeft,
+];
+
+impl Shape {
+    pub fn label(self) -> String {
+        match self {
+            Shape::Square(size) => format!("{size}×{size}"),
+            Shape::Hex(radius) => format!("Hex radius {radius}"),
+        }
+    }
+
+    /// Directions tiles can be moved in on this shape.
+    pub fn directions(self) -> &'static [Direction] {
+        match self {
+            Shape::Square(_) => &SQUARE_DIRECTIONS,
+            Shape::Hex(_) => &HEX_DIRECTIONS,
+        }
+    }
+
+    /// Coordinates of every cell in storage order: `(row, col)` for squares
+    /// and axial `(q, r)` for hexes.
+    pub fn coordinates(self) -> Vec<(i32, i32)> {
+        match self {
+            Shape::Square(size) => {
+                let size = size as i32;
+                (0..size)
+                    .flat_map(|row| (0..size).map(move |col| (row, col)))
+                    .collect()
+            }
+            Shape::Hex(radius) => {
+                let radius = radius as i32;
+                (-radius..=radius)
+                    .flat_map(|q| {
+                        let low = (-radius).max(-q - radius);
+                        let high = radius.min(-q + radius);
+                        (low..=high).map(move |r| (q, r))
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// Cell indices grouped into display rows: grid rows for squares, and
+    /// cells of equal `r` for hexes.
+    pub fn rows(self) -> Vec<Vec<usize>> {
+        match self {
+            Shape::Square(size) => (0..size)
+                .map(|row| (row * size..(row + 1) * size).collect())
+                .collect(),
+            Shape::Hex(radius) => {
+                let mut rows = vec![Vec::new(); 2 * radius + 1];
+                for (i, (_, r)) in self.coordinates().into_iter().enumerate() {
+                    rows[(r + radius as i32) as usize].push(i);
+                }
+                rows
+            }
+        }
+    }
+
+    pub fn cell_count(self) -> usize {
+        match self {
+            Shape::Square(size) => size * size,
+            Shape::Hex(radius) => 3 * radius * (radius + 1) + 1,
+        }
+    }
+
+    /// Coordinate step for one cell in `direction`, if it exists on this shape.
+    fn step(self, direction: Direction) -> Option<(i32, i32)> {
+        match (self, direction) {
+            (Shape::Square(_), Direction::Up) => Some((-1, 0)),
+            (Shape::Square(_), Direction::Down) => Some((1, 0)),
+            (Shape::Square(_), Direction::Left) => Some((0, -1)),
+            (Shape::Square(_), Direction::Right) => Some((0, 1)),
+            (Shape::Hex(_), Direction::Up) => Some((0, -1)),
+            (Shape::Hex(_), Direction::Down) => Some((0, 1)),
+            (Shape::Hex(_), Direction::UpRight) => Some((1, -1)),
+            (Shape::Hex(_), Direction::DownRight) => Some((1, 0)),
+            (Shape::Hex(_), Direction::DownLeft) => Some((-1, 1)),
+            (Shape::Hex(_), Direction::UpLeft) => Some((-1, 0)),
+            _ => None,
+        }
+    }
+}
+
+/// The cells of a board, stored in the order given by [`Shape::coordinates`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "BoardRepr", try_from = "BoardRepr")]
+pub struct Board {
+    shape: Shape,
+    cells: Vec<Cell>,
+}
+
+impl Board {
+    pub fn new(shape: Shape) -> Self {
+        Board {
+            shape,
+            cells: vec![Cell::Empty; shape.cell_count()],
+        }
+    }
+
+    pub fn shape(&self) -> Shape {
+        self.shape
+    }
+
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    /// Cells grouped into display rows, as given by [`Shape::rows`].
+    pub fn rows(&self) -> Vec<Vec<Cell>> {
+        self.shape
+            .rows()
+            .into_iter()
+            .map(|row| row.into_iter().map(|i| self.cells[i]).collect())
+            .collect()
+    }
+
+    /// Every line of cells for a move in `direction`, each ordered from the
+    /// edge the tiles slide towards. Empty if the shape has no such direction.
+    pub fn lines(&self, direction: Direction) -> &'static [Vec<usize>] {
+        let key = (self.shape, direction);
+        let cache = LINES.get_or_init(RwLock::default);
+        if let Some(lines) = cache.read().unwrap().get(&key) {
+            return lines;
+        }
+        let mut cache = cache.write().unwrap();
+        let lines: &'static [Vec<usize>] = cache
+            .entry(key)
+            .or_insert_with(|| build_lines(self.shape, direction).leak());
+        lines
+    }
+}
+
+/// Lines of every shape and direction played so far. Every move and game
+/// over check walks them, and there are only a handful of shapes, so each
+/// is worked out once and kept.
+type LineCache = RwLock<HashMap<(Shape, Direction), &'static [Vec<usize>]>>;
+
+static LINES: OnceLock<LineCache> = OnceLock::new();
+
+fn build_lines(shape: Shape, direction: Direction) -> Vec<Vec<usize>> {
+    let Some((dx, dy)) = shape.step(direction) else {
+        return Vec::new();
+    };
+    let coordinates = shape.coordinates();
+    let index: HashMap<(i32, i32), usize> = coordinates
+        .iter()
+        .enumerate()
+        .map(|(i, &coordinate)| (coordinate, i))
+        .collect();
+
+    coordinates
+        .iter()
+        .filter(|&&(x, y)| !index.contains_key(&(x + dx, y + dy)))
+        .map(|&(x, y)| {
+            let mut line = Vec::new();
+            let (mut x, mut y) = (x, y);
+            while let Some(&i) = index.get(&(x, y)) {
+                line.push(i);
+                (x, y) = (x - dx, y - dy);
+            }
+            line
+        })
+        .collect()
+}
+
+impl Index<usize> for Board {
+    type Output = Cell;
+
+    fn index(&self, index: usize) -> &Cell {
+        &self.cells[index]
+    }
+}
+
+impl IndexMut<usize> for Board {
+    fn index_mut(&mut self, index: usize) -> &mut Cell {
+        &mut self.cells[index]
+    }
+}
+
+/// Saved form of a board. Square boards are nested rows, matching saves
+/// from before other shapes existed.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum BoardRepr {
+    Hex { radius: usize, cells: Vec<Cell> },
+    Square(Vec<Vec<Cell>>),
+}
+
+impl From<Board> for BoardRepr {
+    fn from(board: Board) -> Self {
+        match board.shape {
+            Shape::Square(_) => BoardRepr::Square(board.rows()),
+            Shape::Hex(radius) => BoardRepr::Hex {
+                radius,
+                cells: board.cells,
+            },
+        }
+    }
+}
+
+impl TryFrom<BoardRepr> for Board {
+    type Error = String;
+
+    fn try_from(repr: BoardRepr) -> Result<Self, Self::Error> {
+        let (shape, cells) = match repr {
+            BoardRepr::Square(rows) => {
+                let size = rows.len();
+                if rows.iter().any(|row| row.len() != size) {
+                    return Err("square board rows must all be as long as the board is tall".into());
+                }
+                (Shape::Square(size), rows.concat())
+            }
+            BoardRepr::Hex { radius, cells } => (Shape::Hex(radius), cells),
+        };
+        if cells.len() != shape.cell_count() {
+            return Err(format!(
+                "{} board needs {} cells, found {}",
+                shape.label(),
+                shape.cell_count(),
+                cells.len()
+            ));
+        }
+        Ok(Board { shape, cells })
+    }
+}
@@ -0,0 +1,494 @@
+//! The command line: flags for the game window, and subcommands that work
+//! without one.
+
+use crate::daily::DailyLog;
+use crate::leaderboard::Leaderboard;
+use crate::puzzles::PuzzleLibrary;
+use crate::settings::{Settings, Theme};
+use clap::builder::PossibleValuesParser;
+use clap::error::ErrorKind;
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use iced_2048::board::Shape;
+use iced_2048::date::Date;
+use iced_2048::export::{self, ReplayOptions};
+use iced_2048::game::{Game, Mode, UndoPolicy, Variant};
+use iced_2048::notation::{self, Position, Record};
+use iced_2048::paths;
+use iced_2048::rng::SpawnRng;
+use iced_2048::rules::MergeRule;
+use iced_2048::simulate::{self, STRATEGY_NAMES, StrategyOptions, head_to_head};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "iced-2048",
+    version,
+    about = "2048 with variants, puzzles, races and analysis"
+)]
+pub struct Cli {
+    #[command(flatten)]
+    pub dirs: DirArgs,
+    #[command(flatten)]
+    pub play: PlayArgs,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+impl Cli {
+    /// Parses the command line, exiting with usage help if it's wrong.
+    pub fn parse_args() -> Self {
+        let cli = Cli::parse();
+        // Only the directory flags are shared with subcommands
+        if cli.command.is_some() && cli.play != PlayArgs::default() {
+            Cli::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "game flags go after `play`, or without a subcommand",
+                )
+                .exit();
+        }
+        cli
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Open the game window (the default)
+    Play(PlayArgs),
+    /// Play strategies against each other without a window
+    Sim(SimArgs),
+    /// Check a game record and print where it ends, or animate it
+    Replay(ReplayArgs),
+    /// Print high scores, daily results and solved puzzles
+    Stats,
+    /// Render a board to a PNG or SVG image
+    Export(ExportArgs),
+    /// Print where the game keeps its data and config
+    Paths,
+    /// Print a shell completion script
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+}
+
+/// Where files go, for every subcommand; see [`iced_2048::paths`].
+#[derive(Debug, Clone, Default, Args)]
+pub struct DirArgs {
+    /// Keep saves, scores and puzzles here, and the config too unless
+    /// --config-dir says otherwise [env: ICED_2048_DATA_DIR]
+    #[arg(long, global = true, value_name = "DIR")]
+    data_dir: Option<PathBuf>,
+    /// Keep settings here [env: ICED_2048_CONFIG_DIR]
+    #[arg(long, global = true, value_name = "DIR")]
+    config_dir: Option<PathBuf>,
+    /// Keep everything next to the executable, as a file named `portable`
+    /// there also does
+    #[arg(long, global = true)]
+    portable: bool,
+}
+
+impl DirArgs {
+    pub fn overrides(&self) -> paths::Overrides {
+        paths::Overrides {
+            data_dir: self.data_dir.clone(),
+            config_dir: self.config_dir.clone(),
+            portable: self.portable,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Args)]
+pub struct PlayArgs {
+    /// Board of a new game: 4 or 4x4 for squares, hex2 for hexagons
+    #[arg(long, value_parser = parse_size)]
+    pub size: Option<Shape>,
+    /// Merge rule of a new game: std, fib, threes or base3
+    #[arg(long, value_parser = notation::parse_rule)]
+    pub rule: Option<MergeRule>,
+    /// Seed for the spawns of a new game, to play the same game again
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Mode of a new game: classic, daily, daily:YYYY-MM-DD,
+    /// time-attack:MINUTES or moves:LIMIT
+    #[arg(long, value_parser = parse_mode)]
+    pub mode: Option<Mode>,
+    /// Colors, instead of those of the theme setting
+    #[arg(long, value_enum)]
+    pub theme: Option<Theme>,
+    /// Save the game to this file instead of the data directory
+    #[arg(long, value_name = "PATH")]
+    pub save_file: Option<PathBuf>,
+    /// Neither load nor save the game
+    #[arg(long, conflicts_with = "save_file")]
+    pub no_save: bool,
+    /// Start from a position or game record in text notation, or a file
+    /// holding one
+    #[arg(
+        long,
+        value_name = "NOTATION",
+        visible_alias = "position",
+        conflicts_with_all = ["size", "rule", "seed", "mode"]
+    )]
+    pub load: Option<String>,
+    /// Print a sentence on stdout for every move and board read out, for a
+    /// screen reader or speech synthesizer to speak
+    #[arg(long)]
+    pub announce: bool,
+}
+
+impl PlayArgs {
+    /// Points saving where the flags say. Comes before anything loads the
+    /// saved game, settings included.
+    pub fn use_save_file(&self) {
+        if self.no_save {
+            Game::set_save_file(None);
+        } else if let Some(path) = &self.save_file {
+            Game::set_save_file(Some(path.clone()));
+        }
+    }
+
+    /// Picks the game to open with: the loaded position, a new game when
+    /// any of its settings were given, or else the saved game. What the
+    /// flags leave out comes from the settings.
+    pub fn start(&self, settings: &Settings) -> Result<Game, String> {
+        let dark_mode = settings.is_dark(self.theme.unwrap_or(settings.theme));
+        if let Some(notation) = &self.load {
+            return load_record(notation)?.replay(settings.undo_policy, dark_mode);
+        }
+        let new_game = self.size.is_some()
+            || self.rule.is_some()
+            || self.seed.is_some()
+            || self.mode.is_some();
+        if !new_game {
+            let mut game = Game::load().unwrap_or_else(|| {
+                let variant = Variant {
+                    shape: settings.shape,
+                    ..Variant::default()
+                };
+                Game::new_with_mode(Mode::Classic, settings.undo_policy, variant, dark_mode)
+            });
+            game.dark_mode = dark_mode;
+            return Ok(game);
+        }
+
+        let mode = self.mode.unwrap_or_default();
+        if let Mode::Daily(date) = mode {
+            if self.size.is_some() || self.rule.is_some() || self.seed.is_some() {
+                return Err("the daily challenge takes no --size, --rule or --seed".to_string());
+            }
+            // There's only one attempt at a daily challenge: carry on with
+            // the saved one, as the Daily button does, or refuse
+            if DailyLog::load().get(date).is_some() {
+                return match Game::load() {
+                    Some(mut game) if game.mode == mode => {
+                        game.dark_mode = dark_mode;
+                        Ok(game)
+                    }
+                    _ => Err(format!("the daily challenge of {date} was already played")),
+                };
+            }
+        }
+        let variant = Variant {
+            shape: self.size.unwrap_or(settings.shape),
+            rule: self.rule.unwrap_or_default(),
+            ..Variant::default()
+        };
+        Ok(match self.seed {
+            Some(seed) => {
+                // Ranked modes are a single attempt, as in Game::new_with_mode
+                let undo_policy = match mode {
+                    Mode::Classic => settings.undo_policy,
+                    _ => UndoPolicy::Disabled,
+                };
+                let rng = Some(SpawnRng::new(seed));
+                Game::build(mode, undo_policy, variant, dark_mode, rng)
+            }
+            None => Game::new_with_mode(mode, settings.undo_policy, variant, dark_mode),
+        })
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct SimArgs {
+    /// Strategies to compare
+    #[arg(
+        long,
+        value_delimiter = ',',
+        default_value = "greedy,montecarlo",
+        value_parser = PossibleValuesParser::new(STRATEGY_NAMES)
+    )]
+    strategies: Vec<String>,
+    /// Games for each strategy
+    #[arg(long, default_value_t = 100, value_parser = clap::value_parser!(u64).range(1..))]
+    games: u64,
+    /// Seed of the first game; the next ones count up from it
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+    /// Board to play on
+    #[arg(long, value_parser = parse_size)]
+    size: Option<Shape>,
+    /// Merge rule to play with
+    #[arg(long, value_parser = notation::parse_rule)]
+    rule: Option<MergeRule>,
+    /// Monte Carlo playouts per move
+    #[arg(long, default_value_t = 100)]
+    rollouts: u32,
+    /// Caps Monte Carlo's thinking per move, in milliseconds
+    #[arg(long, value_name = "MS")]
+    time_budget: Option<u64>,
+    /// Monte Carlo threads, all cores by default
+    #[arg(long)]
+    threads: Option<usize>,
+    /// N-tuple weights, instead of ntuple.bin in the data directory
+    #[arg(long, value_name = "PATH")]
+    weights: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct ReplayArgs {
+    /// A game record in text notation, or a save file
+    file: PathBuf,
+    /// Animate the game into this GIF or APNG (.gif, .png or .apng)
+    #[arg(long, value_name = "PATH")]
+    export: Option<PathBuf>,
+    /// Milliseconds each move stays on screen, by default as the speed
+    /// setting says
+    #[arg(long, value_name = "MS", requires = "export")]
+    delay: Option<u64>,
+    /// Colors of the animation, by default those of the theme setting
+    #[arg(long, value_enum, requires = "export")]
+    theme: Option<Theme>,
+    /// Leave the score out of the animation
+    #[arg(long, requires = "export")]
+    no_score: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ExportArgs {
+    /// The image to write, .png or .svg
+    output: PathBuf,
+    /// Render this position or game record, or a file holding one, instead
+    /// of the saved game
+    #[arg(long, value_name = "NOTATION", visible_alias = "position")]
+    load: Option<String>,
+    /// Colors, instead of those of the theme setting
+    #[arg(long, value_enum)]
+    theme: Option<Theme>,
+}
+
+/// Runs a subcommand other than `play`.
+pub fn run(command: Command) -> Result<(), String> {
+    match command {
+        Command::Play(_) => unreachable!("play opens the window"),
+        Command::Sim(args) => sim(args),
+        Command::Replay(args) => replay(args),
+        Command::Stats => {
+            stats();
+            Ok(())
+        }
+        Command::Export(args) => {
+            let settings = Settings::load();
+            let dark_mode = settings.is_dark(args.theme.unwrap_or(settings.theme));
+            let game = match &args.load {
+                Some(notation) => load_record(notation)?.replay(settings.undo_policy, dark_mode)?,
+                None => Game::load().unwrap_or_default(),
+            };
+            export::save_board(&game.board, game.variant.rule, dark_mode, &args.output)
+        }
+        Command::Paths => {
+            let dirs = paths::dirs();
+            let show = |folder: &Result<PathBuf, String>| match folder {
+                Ok(folder) => folder.display().to_string(),
+                Err(error) => format!("none ({error})"),
+            };
+            println!("Data:   {}", show(&dirs.data));
+            println!("Config: {}", show(&dirs.config));
+            if dirs.portable {
+                println!("Portable mode is on");
+            }
+            Ok(())
+        }
+        Command::Completions { shell } => {
+            clap_complete::generate(
+                shell,
+                &mut Cli::command(),
+                "iced-2048",
+                &mut std::io::stdout(),
+            );
+            Ok(())
+        }
+    }
+}
+
+fn sim(args: SimArgs) -> Result<(), String> {
+    let options = StrategyOptions {
+        rollouts: args.rollouts,
+        time_budget: args.time_budget.map(Duration::from_millis),
+        threads: args.threads,
+        weights: args.weights,
+    };
+    let mut strategies = args
+        .strategies
+        .iter()
+        .map(|name| simulate::strategy(name, &options))
+        .collect::<Result<Vec<_>, _>>()?;
+    let variant = Variant {
+        shape: args.size.unwrap_or_default(),
+        rule: args.rule.unwrap_or_default(),
+        ..Variant::default()
+    };
+    let last_seed = args
+        .seed
+        .checked_add(args.games)
+        .ok_or("--seed is too large for that many games")?;
+    let seeds = args.seed..last_seed;
+    let summaries = head_to_head(&mut strategies, variant, seeds, |played| {
+        if played.is_multiple_of(10) {
+            eprintln!("{played}/{} games", args.games);
+        }
+    });
+    print!("{}", simulate::table(&summaries));
+    Ok(())
+}
+
+fn replay(args: ReplayArgs) -> Result<(), String> {
+    let record =
+        Record::load(&args.file).map_err(|error| format!("{}: {error}", args.file.display()))?;
+    match &args.export {
+        Some(path) => {
+            let settings = Settings::load();
+            let options = ReplayOptions {
+                delay: args
+                    .delay
+                    .map_or(settings.speed.replay_delay(), Duration::from_millis),
+                dark_mode: settings.is_dark(args.theme.unwrap_or(settings.theme)),
+                score: !args.no_score,
+            };
+            export::save_replay(&record, &options, path)
+        }
+        None => {
+            let game = record.replay(UndoPolicy::Disabled, false)?;
+            println!("{}", Position::of(&game));
+            let state = if game.game_over {
+                "game over"
+            } else {
+                "in play"
+            };
+            println!(
+                "{} points in {} moves, best tile {}, {state}",
+                game.score,
+                game.moves,
+                game.max_tile()
+            );
+            Ok(())
+        }
+    }
+}
+
+fn stats() {
+    match Game::load() {
+        Some(game) => println!(
+            "Saved game: {}, {}: {} points in {} moves, best tile {}",
+            game.mode.label(),
+            game.variant.label(),
+            game.score,
+            game.moves,
+            game.max_tile()
+        ),
+        None => println!("No saved game"),
+    }
+
+    let leaderboard = Leaderboard::load();
+    let mut tables = leaderboard.tables().peekable();
+    if tables.peek().is_none() {
+        println!("No high scores yet");
+    } else {
+        println!("High scores:");
+    }
+    for (key, entries) in tables {
+        if let Some(best) = entries.first() {
+            println!(
+                "  {key:<32} {:>8} points, tile {:>5}, {} ({} games)",
+                best.score,
+                best.max_tile,
+                best.date,
+                entries.len()
+            );
+        }
+    }
+
+    let daily = DailyLog::load();
+    let played = daily.results().count();
+    let best = daily.results().max_by_key(|(_, result)| result.score);
+    match best {
+        Some((date, result)) => {
+            let won = daily.results().filter(|(_, result)| result.won).count();
+            println!(
+                "Daily challenges: {played} played, {won} won, best {} on {date}",
+                result.score
+            );
+        }
+        None => println!("No daily challenges played"),
+    }
+
+    println!("Puzzles solved: {}", PuzzleLibrary::load().solved_count());
+}
+
+/// A record from text notation, or from the file it names.
+fn load_record(notation: &str) -> Result<Record, String> {
+    let path = Path::new(notation);
+    if path.is_file() {
+        Record::load(path).map_err(|error| format!("{}: {error}", path.display()))
+    } else {
+        Record::parse(notation)
+    }
+}
+
+/// A board size: a square's side, a shape as written in positions (`4x4`,
+/// `hex2`), within the sizes the window can show.
+fn parse_size(text: &str) -> Result<Shape, String> {
+    match text.parse() {
+        Ok(size) => notation::playable(Shape::Square(size)),
+        Err(_) => notation::parse_shape(text),
+    }
+}
+
+fn parse_mode(text: &str) -> Result<Mode, String> {
+    let (name, value) = match text.split_once(':') {
+        Some((name, value)) => (name, Some(value)),
+        None => (text, None),
+    };
+    let number = |example: &str| -> Result<u32, String> {
+        let value = value.ok_or_else(|| format!("{name} needs a number, as in {example}"))?;
+        match value.parse() {
+            Ok(number) if number > 0 => Ok(number),
+            _ => Err(format!("invalid limit for {name}: {value}")),
+        }
+    };
+    match (name, value) {
+        ("classic", None) => Ok(Mode::Classic),
+        ("daily", None) => Ok(Mode::Daily(Date::today())),
+        ("daily", Some(date)) => match date.parse()? {
+            date if date > Date::today() => {
+                Err(format!("the daily challenge of {date} isn't out yet"))
+            }
+            date => Ok(Mode::Daily(date)),
+        },
+        ("time-attack", _) => {
+            let seconds = number("time-attack:3")?
+                .checked_mul(60)
+                .ok_or_else(|| format!("invalid limit for {name}: too many minutes"))?;
+            Ok(Mode::TimeAttack { seconds })
+        }
+        ("moves", _) => Ok(Mode::LimitedMoves {
+            moves: number("moves:100")?,
+        }),
+        _ => Err(format!(
+            "unknown mode {text}; use classic, daily, daily:YYYY-MM-DD, time-attack:MINUTES \
+             or moves:LIMIT"
+        )),
+    }
+}
@@ -0,0 +1,214 @@
+use crate::Message;
+use crate::focus::{focusable, focusable_maybe};
+use iced::widget::{button, column, container, row, text};
+use iced::{Element, Length};
+use iced_2048::board::Board;
+use iced_2048::date::{Date, days_in_month};
+use iced_2048::game::{Cell, Game};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyResult {
+    pub score: u32,
+    pub max_tile: u32,
+    pub won: bool,
+    pub finished: bool,
+    pub board: Board,
+}
+
+impl DailyResult {
+    pub fn of(game: &Game) -> Self {
+        DailyResult {
+            score: game.score,
+            max_tile: game.max_tile(),
+            won: game.won,
+            finished: game.game_over,
+            board: game.board.clone(),
+        }
+    }
+
+    /// Text summary suitable for pasting into chat.
+    pub fn share_text(&self, date: Date) -> String {
+        let mut share = format!(
+            "iced-2048 Daily {date}\nScore: {}\nMax tile: {}\n",
+            self.score, self.max_tile
+        );
+        for board_row in self.board.rows() {
+            share.extend(board_row.iter().map(|&cell| tile_emoji(cell)));
+            share.push('\n');
+        }
+        share
+    }
+}
+
+fn tile_emoji(cell: Cell) -> char {
+    match cell {
+        Cell::Empty => '⬜',
+        Cell::Block => '⬛',
+        Cell::Tile(2 | 4) => '🟫',
+        Cell::Tile(8 | 16) => '🟧',
+        Cell::Tile(32 | 64) => '🟥',
+        Cell::Tile(128 | 256) => '🟨',
+        Cell::Tile(512 | 1024) => '🟩',
+        Cell::Tile(2048) => '🟦',
+        Cell::Tile(_) => '🟪',
+    }
+}
+
+/// Results of every daily challenge played on this machine, keyed by date.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DailyLog {
+    results: BTreeMap<Date, DailyResult>,
+}
+
+impl DailyLog {
+    pub fn get(&self, date: Date) -> Option<&DailyResult> {
+        self.results.get(&date)
+    }
+
+    /// Every day played, oldest first.
+    pub fn results(&self) -> impl Iterator<Item = (Date, &DailyResult)> {
+        self.results.iter().map(|(&date, result)| (date, result))
+    }
+
+    pub fn record(&mut self, date: Date, result: DailyResult) {
+        self.results.insert(date, result);
+    }
+
+    pub fn save(&self) {
+        if let Some(path) = iced_2048::data_file_path("daily.json")
+            && let Ok(json) = serde_json::to_string_pretty(self)
+        {
+            let _ = fs::write(path, json);
+        }
+    }
+
+    pub fn load() -> Self {
+        iced_2048::data_file_path("daily.json")
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+}
+
+const MONTH_NAMES: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// Width of a day's button with room for its focus ring.
+const DAY_WIDTH: u16 = 76;
+/// The ring's room already spaces the days out.
+const DAY_SPACING: u16 = 0;
+
+/// Month grid of past daily results, Monday first.
+pub fn view_calendar(
+    log: &DailyLog,
+    (year, month): (i32, u32),
+    selected: Option<Date>,
+) -> Element<'_, Message> {
+    let title = text(format!("{} {year}", MONTH_NAMES[month as usize - 1])).size(30);
+    let navigation = row![
+        focusable(button("◀").padding(8), Message::CalendarMonth(-1)),
+        title,
+        focusable(button("▶").padding(8), Message::CalendarMonth(1)),
+    ]
+    .spacing(20)
+    .align_y(iced::Alignment::Center);
+
+    let weekdays = row(["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"]
+        .into_iter()
+        .map(|name| {
+            container(text(name).size(14))
+                .width(DAY_WIDTH)
+                .align_x(iced::Alignment::Center)
+                .into()
+        }))
+    .spacing(DAY_SPACING);
+
+    let first = Date {
+        year,
+        month,
+        day: 1,
+    };
+    let mut cells: Vec<Element<Message>> = (0..first.weekday())
+        .map(|_| container(text("")).width(DAY_WIDTH).into())
+        .collect();
+    for day in 1..=days_in_month(year, month) {
+        let date = Date { year, month, day };
+        let result = log.get(date);
+        let label = match result {
+            Some(result) => format!("{day}\n{}", result.score),
+            None => format!("{day}\n"),
+        };
+        let style = match result {
+            Some(result) if result.won => button::success,
+            Some(_) => button::primary,
+            None => button::secondary,
+        };
+        let cell = button(text(label).size(14).align_x(iced::Alignment::Center))
+            .width(70)
+            .style(style);
+        cells.push(focusable_maybe(
+            cell,
+            result.map(|_| Message::SelectDay(date)),
+        ));
+    }
+
+    let mut weeks = column![weekdays].spacing(DAY_SPACING);
+    while !cells.is_empty() {
+        let rest = cells.split_off(cells.len().min(7));
+        weeks = weeks.push(row(std::mem::replace(&mut cells, rest)).spacing(DAY_SPACING));
+    }
+
+    let details: Element<Message> = match selected.and_then(|date| Some((date, log.get(date)?))) {
+        Some((date, result)) => column![
+            text(format!(
+                "{date}: score {}, max tile {}{}",
+                result.score,
+                result.max_tile,
+                if result.finished {
+                    ""
+                } else {
+                    " (in progress)"
+                }
+            ))
+            .size(18),
+            focusable(
+                button("Copy result").padding(12),
+                Message::CopyDailyShare(date)
+            ),
+        ]
+        .spacing(12)
+        .align_x(iced::Alignment::Center)
+        .into(),
+        None => text("Select a played day to see its result.")
+            .size(16)
+            .into(),
+    };
+
+    let back_button = focusable(button("Back").padding(12), Message::ShowGame);
+
+    let content = column![navigation, weeks, details, back_button]
+        .spacing(20)
+        .align_x(iced::Alignment::Center);
+
+    container(content)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center(Length::Fill)
+        .padding(20)
+        .into()
+}
@@ -0,0 +1,110 @@
+//! Plain sentences about the board and each move, for screen readers and
+//! anything else that can't see the tiles, such as
+//! "Moved left, merged two 8s into 16, score 1204".
+
+use crate::board::Board;
+use crate::game::{Cell, Direction, Game};
+use crate::rules::MergeRule;
+
+/// The board row by row, top first: "Row 1: 2, empty, empty, 4. Row 2: …".
+pub fn board(board: &Board) -> String {
+    board
+        .rows()
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let cells: Vec<String> = row.iter().map(|&cell| self::cell(cell)).collect();
+            format!("Row {}: {}.", i + 1, cells.join(", "))
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The game at a glance, then its board, as read out on request.
+pub fn game(game: &Game) -> String {
+    let status = if game.game_over {
+        ", game over"
+    } else if game.won {
+        ", won"
+    } else {
+        ""
+    };
+    format!(
+        "Score {}, {} {} played, highest tile {}{status}. {}",
+        game.score,
+        game.moves,
+        if game.moves == 1 { "move" } else { "moves" },
+        game.max_tile(),
+        board(&game.board)
+    )
+}
+
+/// What a move just played in `game` did, given the value of each tile its
+/// merges made, from [`Game::slide`].
+pub fn move_result(game: &Game, direction: Direction, merges: &[u32]) -> String {
+    let mut sentence = format!("Moved {}", direction.label());
+    if !merges.is_empty() {
+        sentence += &format!(", merged {}", self::merges(game.variant.rule, merges));
+    }
+    sentence += &format!(", score {}", game.score);
+    if game.game_over {
+        sentence += ", game over";
+    }
+    sentence
+}
+
+/// A move that couldn't be played.
+pub fn blocked(direction: Direction) -> String {
+    format!("Can't move {}", direction.label())
+}
+
+fn cell(cell: Cell) -> String {
+    match cell {
+        Cell::Empty => "empty".to_string(),
+        Cell::Tile(value) => value.to_string(),
+        Cell::Block => "block".to_string(),
+    }
+}
+
+/// "two 8s into 16, 1 and 2 into 3", with repeats counted rather than
+/// listed. Commas, not "and", keep merges of unequal tiles apart.
+fn merges(rule: MergeRule, merges: &[u32]) -> String {
+    let mut counted: Vec<(u32, usize)> = Vec::new();
+    for &value in merges {
+        match counted.iter_mut().find(|(seen, _)| *seen == value) {
+            Some((_, count)) => *count += 1,
+            None => counted.push((value, 1)),
+        }
+    }
+    counted
+        .into_iter()
+        .map(|(value, count)| {
+            let phrase = format!("{} into {value}", parts(&rule.parts(value)));
+            match count {
+                1 => phrase,
+                2 => format!("{phrase} twice"),
+                _ => format!("{phrase} {count} times"),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// "two 8s" for equal tiles, "2 and 3" otherwise.
+fn parts(parts: &[u32]) -> String {
+    match parts {
+        [first, rest @ ..] if rest.iter().all(|part| part == first) => {
+            let count = match parts.len() {
+                2 => "two",
+                3 => "three",
+                _ => "some",
+            };
+            format!("{count} {first}s")
+        }
+        [rest @ .., last] => {
+            let rest: Vec<String> = rest.iter().map(u32::to_string).collect();
+            format!("{} and {last}", rest.join(", "))
+        }
+        [] => String::new(),
+    }
+}
@@ -0,0 +1,490 @@
+use crate::focus::focusable;
+use crate::{Message, options, tile_border_color, tile_colors};
+use iced::widget::{button, column, container, mouse_area, row, text, text_input};
+use iced::{Color, Element, Length, Theme};
+use iced_2048::analysis;
+use iced_2048::board::{Board, Shape};
+use iced_2048::game::{Cell, Direction, Game, SHAPES, UndoPolicy};
+use iced_2048::puzzle::{self, Objective, Pack, Puzzle};
+use iced_2048::rules::{MERGE_RULES, MergeRule};
+use rand::Rng;
+use std::path::Path;
+
+/// Tiles a click steps through before the block; larger ones are typed.
+const CYCLE_TILES: usize = 12;
+
+/// Tiles placed by Fill randomly, from the smallest of the rule.
+const FILL_TILES: usize = 6;
+const FILL_ATTEMPTS: usize = 100;
+
+/// Pack file in the puzzles folder that saved puzzles are added to.
+const PACK_FILE: &str = "my-puzzles.json";
+const PACK_NAME: &str = "My puzzles";
+
+/// The kind of objective a saved puzzle gets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Goal {
+    MakeTile,
+    ClearTo,
+    Score,
+}
+
+const GOALS: [Goal; 3] = [Goal::MakeTile, Goal::ClearTo, Goal::Score];
+
+impl Goal {
+    fn label(self) -> String {
+        match self {
+            Goal::MakeTile => "Make a tile",
+            Goal::ClearTo => "Clear to",
+            Goal::Score => "Score",
+        }
+        .to_string()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Action {
+    /// Left click: the next value for a cell.
+    Cycle(usize),
+    /// Right click: the previous value for a cell.
+    CycleBack(usize),
+    ValueChanged(String),
+    /// Puts the typed value in the selected cell.
+    SetValue,
+    SetShape(Shape),
+    SetRule(MergeRule),
+    /// Starts over from the board of the game being played.
+    FromGame,
+    Clear,
+    Fill,
+    Validate,
+    Analyze,
+    Play,
+    TitleChanged(String),
+    SetGoal(Goal),
+    TargetChanged(String),
+    MovesChanged(String),
+    SavePuzzle,
+}
+
+/// A board being set up by hand, to play from, save as a puzzle or have
+/// the engine analyze.
+pub struct Editor {
+    board: Board,
+    rule: MergeRule,
+    selected: Option<usize>,
+    value: String,
+    /// Result of the last validation, analysis or save.
+    status: Option<String>,
+    /// The engine's values for each legal move, until the board changes.
+    analysis: Vec<(Direction, f64)>,
+    title: String,
+    goal: Goal,
+    target: String,
+    moves: String,
+}
+
+impl Editor {
+    pub fn new(game: &Game) -> Self {
+        Editor {
+            board: game.board.clone(),
+            rule: game.variant.rule,
+            selected: None,
+            value: String::new(),
+            status: None,
+            analysis: Vec::new(),
+            title: String::new(),
+            goal: Goal::MakeTile,
+            target: String::new(),
+            moves: String::new(),
+        }
+    }
+
+    /// Handles every action except playing and saving, which need the rest
+    /// of the app.
+    pub fn update(&mut self, action: Action) {
+        match action {
+            Action::Cycle(i) => {
+                self.selected = Some(i);
+                self.set_cell(i, self.next(self.board[i]));
+            }
+            Action::CycleBack(i) => {
+                self.selected = Some(i);
+                self.set_cell(i, self.previous(self.board[i]));
+            }
+            Action::ValueChanged(value) => self.value = value,
+            Action::SetValue => {
+                let Some(i) = self.selected else {
+                    self.status = Some("Click a cell first".to_string());
+                    return;
+                };
+                match self.parse_value() {
+                    Ok(cell) => self.set_cell(i, cell),
+                    Err(error) => self.status = Some(error),
+                }
+            }
+            Action::SetShape(shape) => {
+                if shape != self.board.shape() {
+                    self.board = Board::new(shape);
+                    self.selected = None;
+                    self.changed();
+                }
+            }
+            Action::SetRule(rule) => {
+                self.rule = rule;
+                self.changed();
+            }
+            Action::Clear => {
+                self.board = Board::new(self.board.shape());
+                self.changed();
+            }
+            Action::Fill => self.fill(),
+            Action::Validate => {
+                self.status = Some(match self.position() {
+                    Ok(_) => "The position is playable".to_string(),
+                    Err(error) => format!("Not playable: {error}"),
+                });
+            }
+            Action::Analyze => match self.position() {
+                Ok(game) => {
+                    self.analysis = analysis::evaluate(&game, &mut analysis::engine());
+                    self.status = self
+                        .analysis
+                        .iter()
+                        .rev()
+                        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+                        .map(|(direction, _)| format!("Engine prefers {}", direction.label()));
+                }
+                Err(error) => self.status = Some(format!("Not playable: {error}")),
+            },
+            Action::TitleChanged(title) => self.title = title,
+            Action::SetGoal(goal) => self.goal = goal,
+            Action::TargetChanged(target) => self.target = target,
+            Action::MovesChanged(moves) => self.moves = moves,
+            // Handled by the app
+            Action::FromGame | Action::Play | Action::SavePuzzle => {}
+        }
+    }
+
+    /// A game from the edited board, or `None` with the reason shown.
+    pub fn play(&mut self, undo_policy: UndoPolicy, dark_mode: bool) -> Option<Game> {
+        match Game::from_position(self.board.clone(), self.rule, undo_policy, dark_mode) {
+            Ok(game) => Some(game),
+            Err(error) => {
+                self.status = Some(format!("Not playable: {error}"));
+                None
+            }
+        }
+    }
+
+    /// Adds the board as a puzzle to the player's own pack in `folder`,
+    /// returning whether it was saved.
+    pub fn save_puzzle(&mut self, folder: Option<&Path>) -> bool {
+        let result = folder
+            .ok_or_else(|| "there's no puzzles folder".to_string())
+            .and_then(|folder| self.add_to_pack(&folder.join(PACK_FILE)));
+        self.status = Some(match &result {
+            Ok(key) => format!("Saved as {key}"),
+            Err(error) => format!("Couldn't save the puzzle: {error}"),
+        });
+        result.is_ok()
+    }
+
+    fn add_to_pack(&self, path: &Path) -> Result<String, String> {
+        let objective = self.objective()?;
+        self.position()?;
+        let title = self.title.trim();
+        if title.is_empty() {
+            return Err("the puzzle needs a title".to_string());
+        }
+
+        let mut pack = if path.exists() {
+            Pack::load(path)?
+        } else {
+            Pack {
+                name: PACK_NAME.to_string(),
+                author: String::new(),
+                puzzles: Vec::new(),
+            }
+        };
+        let id = unique_id(&pack, title);
+        let key = puzzle::key(&pack.name, &id);
+        let puzzle = Puzzle {
+            id,
+            title: title.to_string(),
+            rule: self.rule,
+            board: self.board.clone(),
+            spawns: Vec::new(),
+            objective,
+        };
+        puzzle.validate()?;
+        pack.puzzles.push(puzzle);
+        pack.save(path)?;
+        Ok(key)
+    }
+
+    fn objective(&self) -> Result<Objective, String> {
+        let number = |field: &str, name: &str| {
+            field
+                .trim()
+                .parse::<u32>()
+                .ok()
+                .filter(|&number| number > 0)
+                .ok_or_else(|| format!("{name} must be a positive number"))
+        };
+        let moves = number(&self.moves, "the move limit")?;
+        let target = number(&self.target, "the target")?;
+        Ok(match self.goal {
+            Goal::MakeTile => Objective::MakeTile {
+                value: target,
+                moves,
+            },
+            Goal::ClearTo => Objective::ClearTo {
+                tiles: target as usize,
+                moves,
+            },
+            Goal::Score => Objective::Score {
+                points: target,
+                moves,
+            },
+        })
+    }
+
+    /// The edited board as a game, if it's a playable position.
+    fn position(&self) -> Result<Game, String> {
+        Game::from_position(self.board.clone(), self.rule, UndoPolicy::Disabled, false)
+    }
+
+    fn set_cell(&mut self, i: usize, cell: Cell) {
+        self.board[i] = cell;
+        self.changed();
+    }
+
+    /// Drops results that no longer match the board.
+    fn changed(&mut self) {
+        self.status = None;
+        self.analysis.clear();
+    }
+
+    /// Empty, then the rule's smallest tiles, then a block.
+    fn next(&self, cell: Cell) -> Cell {
+        let tiles: Vec<u32> = self.rule.tiles().take(CYCLE_TILES).collect();
+        match cell {
+            Cell::Empty => Cell::Tile(tiles[0]),
+            Cell::Tile(value) => match tiles.iter().position(|&tile| tile == value) {
+                Some(k) if k + 1 < tiles.len() => Cell::Tile(tiles[k + 1]),
+                _ => Cell::Block,
+            },
+            Cell::Block => Cell::Empty,
+        }
+    }
+
+    fn previous(&self, cell: Cell) -> Cell {
+        let tiles: Vec<u32> = self.rule.tiles().take(CYCLE_TILES).collect();
+        match cell {
+            Cell::Empty => Cell::Block,
+            Cell::Tile(value) => match tiles.iter().position(|&tile| tile == value) {
+                Some(0) => Cell::Empty,
+                Some(k) => Cell::Tile(tiles[k - 1]),
+                None => Cell::Tile(tiles[tiles.len() - 1]),
+            },
+            Cell::Block => Cell::Tile(tiles[tiles.len() - 1]),
+        }
+    }
+
+    /// The typed value as a cell, written as in save files: 0 for empty and
+    /// -1 for a block.
+    fn parse_value(&self) -> Result<Cell, String> {
+        let value = self.value.trim();
+        let cell = value
+            .parse::<i64>()
+            .ok()
+            .and_then(|value| Cell::try_from(value).ok())
+            .ok_or_else(|| format!("{value} isn't a tile value"))?;
+        match cell {
+            Cell::Tile(value) if !self.rule.is_tile(value) => {
+                Err(format!("{value} isn't a tile in {}", self.rule.label()))
+            }
+            cell => Ok(cell),
+        }
+    }
+
+    /// Replaces every cell but the blocks with a random small tile or
+    /// nothing, trying a few times for a playable position.
+    fn fill(&mut self) {
+        let tiles: Vec<u32> = self.rule.tiles().take(FILL_TILES).collect();
+        let mut rng = rand::rng();
+        for _ in 0..FILL_ATTEMPTS {
+            for i in 0..self.board.cells().len() {
+                if self.board[i] != Cell::Block {
+                    self.board[i] = if rng.random_bool(0.5) {
+                        Cell::Tile(tiles[rng.random_range(0..tiles.len())])
+                    } else {
+                        Cell::Empty
+                    };
+                }
+            }
+            if self.position().is_ok() {
+                break;
+            }
+        }
+        self.changed();
+    }
+
+    pub fn view(&self, dark_mode: bool) -> Element<'_, Message> {
+        let title = text("Board Editor").size(40);
+
+        let shapes = options(
+            "Board",
+            &SHAPES,
+            self.board.shape(),
+            Shape::label,
+            |shape| Message::Editor(Action::SetShape(shape)),
+        );
+        let rules = options("Rule", &MERGE_RULES, self.rule, MergeRule::label, |rule| {
+            Message::Editor(Action::SetRule(rule))
+        });
+
+        let board = column(self.board.shape().rows().into_iter().map(|cells| {
+            row(cells.into_iter().map(|i| self.view_cell(i, dark_mode)))
+                .spacing(6)
+                .into()
+        }))
+        .spacing(6)
+        .align_x(iced::Alignment::Center);
+
+        let value_row = row![
+            text_input("Value for the selected cell", &self.value)
+                .on_input(|value| Message::Editor(Action::ValueChanged(value)))
+                .on_submit(Message::Editor(Action::SetValue))
+                .width(240),
+            focusable(button("Set").padding(8), Message::Editor(Action::SetValue)),
+        ]
+        .spacing(8)
+        .align_y(iced::Alignment::Center);
+
+        let edit_buttons = row![
+            editor_button("Clear", Action::Clear),
+            editor_button("Fill randomly", Action::Fill),
+            editor_button("From game", Action::FromGame),
+            editor_button("Validate", Action::Validate),
+            editor_button("Analyze", Action::Analyze),
+            editor_button("Play", Action::Play),
+        ]
+        .spacing(12);
+
+        let mut details = column![].spacing(6);
+        if let Some(status) = &self.status {
+            details = details.push(text(status).size(18));
+        }
+        for (direction, value) in &self.analysis {
+            details = details.push(
+                text(format!("{:<10} {value:>8.0}", direction.label()))
+                    .font(iced::Font::MONOSPACE)
+                    .size(16),
+            );
+        }
+
+        let goals = options("Objective", &GOALS, self.goal, Goal::label, |goal| {
+            Message::Editor(Action::SetGoal(goal))
+        });
+        let puzzle_row = row![
+            text_input("Puzzle title", &self.title)
+                .on_input(|title| Message::Editor(Action::TitleChanged(title)))
+                .width(200),
+            text_input("Target", &self.target)
+                .on_input(|target| Message::Editor(Action::TargetChanged(target)))
+                .width(100),
+            text_input("Moves", &self.moves)
+                .on_input(|moves| Message::Editor(Action::MovesChanged(moves)))
+                .width(100),
+            editor_button("Save as puzzle", Action::SavePuzzle),
+        ]
+        .spacing(8)
+        .align_y(iced::Alignment::Center);
+
+        let content = column![
+            title,
+            shapes,
+            rules,
+            row![board, details].spacing(40),
+            text("Click a cell for the next value, right-click for the previous one").size(14),
+            value_row,
+            edit_buttons,
+            goals,
+            puzzle_row,
+            focusable(button("Back").padding(12), Message::ShowModes),
+        ]
+        .spacing(16)
+        .align_x(iced::Alignment::Center);
+
+        container(content)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .center(Length::Fill)
+            .padding(20)
+            .into()
+    }
+
+    fn view_cell(&self, i: usize, dark_mode: bool) -> Element<'_, Message> {
+        let cell = self.board[i];
+        let label = match cell {
+            Cell::Tile(value) => value.to_string(),
+            Cell::Empty | Cell::Block => String::new(),
+        };
+        let (background, text_color) = tile_colors(cell, self.rule, dark_mode);
+        let selected = self.selected == Some(i);
+        let border_color = if selected {
+            Color::from_rgb(0.2, 0.5, 1.0)
+        } else {
+            tile_border_color(cell, dark_mode)
+        };
+        let tile = container(text(label).size(22).color(text_color))
+            .width(70)
+            .height(70)
+            .center(70)
+            .style(move |_theme: &Theme| container::Style {
+                background: Some(iced::Background::Color(background)),
+                border: iced::Border {
+                    color: border_color,
+                    width: if selected { 4.0 } else { 2.0 },
+                    radius: 8.0.into(),
+                },
+                ..Default::default()
+            });
+        mouse_area(tile)
+            .on_press(Message::Editor(Action::Cycle(i)))
+            .on_right_press(Message::Editor(Action::CycleBack(i)))
+            .into()
+    }
+}
+
+fn editor_button(label: &str, action: Action) -> Element<'_, Message> {
+    focusable(button(label).padding(8), Message::Editor(action))
+}
+
+/// An id for a puzzle titled `title` that no other puzzle in `pack` has.
+fn unique_id(pack: &Pack, title: &str) -> String {
+    let slug: String = title
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let slug = slug
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    let slug = if slug.is_empty() {
+        "puzzle".to_string()
+    } else {
+        slug
+    };
+    (1..)
+        .map(|n| match n {
+            1 => slug.clone(),
+            n => format!("{slug}-{n}"),
+        })
+        .find(|id| pack.get(id).is_none())
+        .unwrap()
+}
@@ -0,0 +1,324 @@
+//! A reinforcement-learning environment in the style of OpenAI Gym.
+//!
+//! ```
+//! use iced_2048::env::{Env, EnvConfig};
+//!
+//! let mut env = Env::new(EnvConfig::default());
+//! let mut observation = env.reset(42);
+//! loop {
+//!     // Take the first legal action; an agent would choose from `observation`
+//!     let action = env.legal_actions().iter().position(|&legal| legal).unwrap();
+//!     let step = env.step(action);
+//!     observation = step.observation;
+//!     if step.done {
+//!         break;
+//!     }
+//! }
+//! assert!(env.game().game_over);
+//! # let _ = observation;
+//! ```
+//!
+//! Actions index into [`Env::actions`], the directions of the board's shape:
+//! up, down, left and right on square boards. Spawns come from the seed given
+//! to [`Env::reset`], so an episode replays exactly from its seed and actions.
+
+use crate::game::{Cell, Direction, Game, Variant};
+use serde::{Deserialize, Serialize};
+
+/// How the board is turned into numbers, one cell at a time in board order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Encoding {
+    /// Tile values as they are: 0 for empty, -1 for a block.
+    Raw,
+    /// Tile ranks: 1 for the smallest tile (2 in the standard game), 2 for
+    /// the next and so on; 0 for empty, -1 for a block and -2 for a value
+    /// the rule can't make.
+    Log2,
+    /// `planes` planes of one value per cell. Plane 0 marks empty cells and
+    /// plane `k` tiles of rank `k`; the last plane also takes every higher
+    /// rank. Blocks and values the rule can't make are 0 on every plane.
+    OneHot { planes: usize },
+}
+
+/// What an agent is rewarded for on each step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Reward {
+    /// Points scored by the move, as in the game.
+    #[default]
+    ScoreDelta,
+    /// Number of merges made by the move.
+    MergeCount,
+    /// 1 for every move that changes the board.
+    Survival,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EnvConfig {
+    pub variant: Variant,
+    pub encoding: Encoding,
+    pub reward: Reward,
+    /// Reward for an action that doesn't change the board, usually 0 or
+    /// negative. The state is left as it was.
+    pub illegal_reward: f32,
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        EnvConfig {
+            variant: Variant::default(),
+            encoding: Encoding::Log2,
+            reward: Reward::ScoreDelta,
+            illegal_reward: 0.0,
+        }
+    }
+}
+
+/// Extra details about a step, beyond what the reward says.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Info {
+    /// Whether the action changed the board.
+    pub moved: bool,
+    pub score: u32,
+    pub moves: u32,
+    pub max_tile: u32,
+    pub won: bool,
+}
+
+/// The result of [`Env::step`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub observation: Vec<f32>,
+    pub reward: f32,
+    pub done: bool,
+    pub info: Info,
+}
+
+#[derive(Debug, Clone)]
+pub struct Env {
+    config: EnvConfig,
+    game: Game,
+}
+
+impl Env {
+    /// An environment with a game seeded from 0; call [`Env::reset`] to
+    /// start an episode from a chosen seed.
+    pub fn new(config: EnvConfig) -> Self {
+        Env {
+            config,
+            game: Game::new_seeded(0, config.variant, false),
+        }
+    }
+
+    pub fn config(&self) -> &EnvConfig {
+        &self.config
+    }
+
+    /// The game being played, for inspection.
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Directions the actions stand for, in action order.
+    pub fn actions(&self) -> &'static [Direction] {
+        self.config.variant.shape.directions()
+    }
+
+    /// Starts a new episode whose spawns all come from `seed`.
+    pub fn reset(&mut self, seed: u64) -> Vec<f32> {
+        self.game = Game::new_seeded(seed, self.config.variant, false);
+        self.observation()
+    }
+
+    /// Plays `action`. Actions that don't change the board, including ones
+    /// out of range, leave the game as it was and earn `illegal_reward`.
+    pub fn step(&mut self, action: usize) -> Step {
+        let direction = self.actions().get(action).copied();
+        let merges = match direction {
+            Some(direction) if !self.game.game_over => self.game.slide(direction).1,
+            _ => Vec::new(),
+        };
+        let score = self.game.score;
+        let moved = direction
+            .is_some_and(|direction| !self.game.game_over && self.game.move_tiles(direction));
+
+        let reward = if !moved {
+            self.config.illegal_reward
+        } else {
+            match self.config.reward {
+                Reward::ScoreDelta => (self.game.score - score) as f32,
+                Reward::MergeCount => merges.len() as f32,
+                Reward::Survival => 1.0,
+            }
+        };
+
+        Step {
+            observation: self.observation(),
+            reward,
+            done: self.game.game_over,
+            info: Info {
+                moved,
+                score: self.game.score,
+                moves: self.game.moves,
+                max_tile: self.game.max_tile(),
+                won: self.game.won,
+            },
+        }
+    }
+
+    /// Which actions would change the board, in action order. All false once
+    /// the game is over.
+    pub fn legal_actions(&self) -> Vec<bool> {
+        self.actions()
+            .iter()
+            .map(|&direction| {
+                !self.game.game_over && self.game.slide(direction).0 != self.game.board
+            })
+            .collect()
+    }
+
+    /// The current board in the configured encoding.
+    pub fn observation(&self) -> Vec<f32> {
+        let cells = self.game.board.cells();
+        match self.config.encoding {
+            Encoding::Raw => cells.iter().map(|&cell| i64::from(cell) as f32).collect(),
+            Encoding::Log2 => cells.iter().map(|&cell| self.rank(cell) as f32).collect(),
+            Encoding::OneHot { planes } => {
+                let mut observation = vec![0.0; planes * cells.len()];
+                for (i, &cell) in cells.iter().enumerate() {
+                    let rank = self.rank(cell);
+                    if rank >= 0 && planes > 0 {
+                        let plane = (rank as usize).min(planes - 1);
+                        observation[plane * cells.len() + i] = 1.0;
+                    }
+                }
+                observation
+            }
+        }
+    }
+
+    /// Rank of a cell: 0 when empty, -1 for a block, and for tiles their
+    /// place among the rule's tiles, from 1. Unlike the palette, this
+    /// keeps every tile apart, such as the 1 and 2 of Threes. A value the
+    /// rule can't make, from a hand-edited position say, is -2.
+    fn rank(&self, cell: Cell) -> i32 {
+        match cell {
+            Cell::Empty => 0,
+            Cell::Block => -1,
+            Cell::Tile(value) => self
+                .config
+                .variant
+                .rule
+                .tiles()
+                .position(|tile| tile == value)
+                .map_or(-2, |position| position as i32 + 1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{Board, Shape};
+
+    /// An environment whose 4×4 board holds `cells`, row by row.
+    fn env_with(config: EnvConfig, cells: [i64; 16]) -> Env {
+        let mut env = Env::new(config);
+        env.reset(1);
+        let mut board = Board::new(Shape::Square(4));
+        for (i, value) in cells.into_iter().enumerate() {
+            board[i] = Cell::try_from(value).unwrap();
+        }
+        env.game.board = board;
+        env
+    }
+
+    /// Two pairs to merge on a move left, into a 4 and an 8.
+    const PAIRS: [i64; 16] = [2, 2, 4, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+    /// A full board where nothing merges.
+    const STUCK: [i64; 16] = [2, 4, 2, 4, 4, 2, 4, 2, 2, 4, 2, 4, 4, 2, 4, 2];
+
+    fn reward_for(reward: Reward) -> f32 {
+        let config = EnvConfig {
+            reward,
+            ..EnvConfig::default()
+        };
+        let left = 2;
+        env_with(config, PAIRS).step(left).reward
+    }
+
+    #[test]
+    fn rewards_follow_the_scheme() {
+        assert_eq!(reward_for(Reward::ScoreDelta), 12.0);
+        assert_eq!(reward_for(Reward::MergeCount), 2.0);
+        assert_eq!(reward_for(Reward::Survival), 1.0);
+    }
+
+    #[test]
+    fn encodes_ranks_and_planes() {
+        let cells = [2, 4, 2048, -1, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        let raw = EnvConfig {
+            encoding: Encoding::Raw,
+            ..EnvConfig::default()
+        };
+        assert_eq!(
+            env_with(raw, cells).observation()[..6],
+            [2.0, 4.0, 2048.0, -1.0, 0.0, 3.0]
+        );
+
+        let log2 = env_with(EnvConfig::default(), cells).observation();
+        assert_eq!(log2[..6], [1.0, 2.0, 11.0, -1.0, 0.0, -2.0]);
+
+        let one_hot = EnvConfig {
+            encoding: Encoding::OneHot { planes: 4 },
+            ..EnvConfig::default()
+        };
+        let observation = env_with(one_hot, cells).observation();
+        assert_eq!(observation.len(), 4 * 16);
+        let planes_of = |cell: usize| -> Vec<f32> {
+            (0..4).map(|plane| observation[plane * 16 + cell]).collect()
+        };
+        assert_eq!(planes_of(0), [0.0, 1.0, 0.0, 0.0]);
+        assert_eq!(planes_of(1), [0.0, 0.0, 1.0, 0.0]);
+        // Ranks past the last plane share it
+        assert_eq!(planes_of(2), [0.0, 0.0, 0.0, 1.0]);
+        // Blocks and values the rule can't make light no plane, unlike empty cells
+        assert_eq!(planes_of(3), [0.0; 4]);
+        assert_eq!(planes_of(4), [1.0, 0.0, 0.0, 0.0]);
+        assert_eq!(planes_of(5), [0.0; 4]);
+    }
+
+    #[test]
+    fn no_action_is_legal_on_a_stuck_board() {
+        let mut env = env_with(EnvConfig::default(), STUCK);
+        assert_eq!(env.legal_actions(), [false; 4]);
+        env.game.game_over = true;
+        assert_eq!(env.legal_actions(), [false; 4]);
+    }
+
+    #[test]
+    fn illegal_actions_leave_the_game_alone() {
+        let config = EnvConfig {
+            illegal_reward: -1.0,
+            ..EnvConfig::default()
+        };
+        // One column down the left edge, alternating so it can't merge
+        let cells = [2, 0, 0, 0, 4, 0, 0, 0, 2, 0, 0, 0, 4, 0, 0, 0];
+        let mut env = env_with(config, cells);
+        assert_eq!(env.legal_actions(), [false, false, false, true]);
+
+        let before = env.game.clone();
+        for action in [0, 1, 2, 4] {
+            let step = env.step(action);
+            assert!(!step.info.moved);
+            assert_eq!(step.reward, -1.0);
+            assert!(!step.done);
+            assert_eq!(step.observation, env.observation());
+            assert_eq!(env.game, before);
+        }
+
+        let step = env.step(3);
+        assert!(step.info.moved);
+        assert_eq!(step.info.moves, 1);
+    }
+}
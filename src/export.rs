@@ -0,0 +1,597 @@
+//! Renders boards to PNG and SVG images, and replays to animated GIF and
+//! APNG, without a window or GPU.
+//!
+//! A board is first laid out as a [`Scene`] of filled and outlined paths, in
+//! the sizes and theme colors of the game window, then rasterized with
+//! `tiny-skia` or written out as SVG. Tile numbers are drawn from the
+//! outlines of a bundled font, so every format looks the same everywhere.
+
+use crate::board::{Board, Shape};
+use crate::game::{Cell, Game, UndoPolicy};
+use crate::notation::Record;
+use crate::palette::{self, Rgb};
+use crate::rules::MergeRule;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tiny_skia::{FillRule, Paint, PathBuilder, Pixmap, Stroke, Transform};
+
+/// Fira Mono Medium, under the SIL Open Font License (`fonts/FiraMono-LICENSE`).
+const FONT: &[u8] = include_bytes!("../fonts/FiraMono-Medium.ttf");
+
+/// Space around the board.
+const MARGIN: f32 = 16.0;
+
+// Square boards, as drawn by the game window
+const CELL: f32 = 90.0;
+const GAP: f32 = 8.0;
+const TEXT_SIZE: f32 = 28.0;
+
+// Hex boards, as drawn by the game window
+const HEX_BOARD_HEIGHT: f32 = 420.0;
+/// Share of a hex cell's size drawn, leaving a gap between neighbours.
+const HEX_CORNER: f32 = 0.92;
+const HEX_TEXT: f32 = 0.55;
+
+/// Height of the score line above replay frames.
+const OVERLAY_HEIGHT: f32 = 40.0;
+const OVERLAY_TEXT: f32 = 22.0;
+/// How long a replay shows the final position before starting over.
+const FINAL_FRAME: Duration = Duration::from_secs(2);
+/// Trades GIF color quality for speed, from 1 (best) to 30.
+const GIF_QUANTIZER_SPEED: i32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// A still image, or an APNG for replays.
+    Png,
+    Svg,
+    Gif,
+}
+
+impl Format {
+    /// The format matching a file's extension.
+    pub fn of_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "png" | "apng" => Some(Format::Png),
+            "svg" => Some(Format::Svg),
+            "gif" => Some(Format::Gif),
+            _ => None,
+        }
+    }
+}
+
+/// How a replay is animated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReplayOptions {
+    /// How long each move stays on screen.
+    pub delay: Duration,
+    pub dark_mode: bool,
+    /// Shows the score and move number above the board.
+    pub score: bool,
+}
+
+impl Default for ReplayOptions {
+    fn default() -> Self {
+        ReplayOptions {
+            delay: Duration::from_millis(250),
+            dark_mode: false,
+            score: true,
+        }
+    }
+}
+
+/// A step of a path outline.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Segment {
+    MoveTo(f32, f32),
+    LineTo(f32, f32),
+    QuadTo(f32, f32, f32, f32),
+    CubicTo(f32, f32, f32, f32, f32, f32),
+    Close,
+}
+
+/// A path with its fill and outline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item {
+    pub path: Vec<Segment>,
+    pub fill: Option<Rgb>,
+    /// Outline color and width.
+    pub stroke: Option<(Rgb, f32)>,
+}
+
+/// An image to render: paths drawn in order over a background.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scene {
+    pub width: f32,
+    pub height: f32,
+    pub background: Rgb,
+    pub items: Vec<Item>,
+}
+
+impl Scene {
+    pub fn new(width: f32, height: f32, background: Rgb) -> Self {
+        Scene {
+            width,
+            height,
+            background,
+            items: Vec::new(),
+        }
+    }
+
+    /// Moves every item by `(dx, dy)`.
+    pub fn translate(mut self, dx: f32, dy: f32) -> Self {
+        for item in &mut self.items {
+            for segment in &mut item.path {
+                *segment = match *segment {
+                    Segment::MoveTo(x, y) => Segment::MoveTo(x + dx, y + dy),
+                    Segment::LineTo(x, y) => Segment::LineTo(x + dx, y + dy),
+                    Segment::QuadTo(x1, y1, x, y) => {
+                        Segment::QuadTo(x1 + dx, y1 + dy, x + dx, y + dy)
+                    }
+                    Segment::CubicTo(x1, y1, x2, y2, x, y) => {
+                        Segment::CubicTo(x1 + dx, y1 + dy, x2 + dx, y2 + dy, x + dx, y + dy)
+                    }
+                    Segment::Close => Segment::Close,
+                };
+            }
+        }
+        self
+    }
+
+    /// Adds `text` in `color`, centred on `(x, y)`.
+    pub fn text(&mut self, text: &str, x: f32, y: f32, size: f32, color: Rgb) {
+        let path = text_path(text, x, y, size);
+        if !path.is_empty() {
+            self.items.push(Item {
+                path,
+                fill: Some(color),
+                stroke: None,
+            });
+        }
+    }
+
+    pub fn to_pixmap(&self) -> Result<Pixmap, String> {
+        let mut pixmap = Pixmap::new(self.width.ceil() as u32, self.height.ceil() as u32)
+            .ok_or("the image is empty")?;
+        pixmap.fill(skia_color(self.background));
+        for item in &self.items {
+            let Some(path) = skia_path(&item.path) else {
+                continue;
+            };
+            let mut paint = Paint {
+                anti_alias: true,
+                ..Paint::default()
+            };
+            if let Some(fill) = item.fill {
+                paint.set_color(skia_color(fill));
+                pixmap.fill_path(
+                    &path,
+                    &paint,
+                    FillRule::Winding,
+                    Transform::identity(),
+                    None,
+                );
+            }
+            if let Some((color, width)) = item.stroke {
+                paint.set_color(skia_color(color));
+                let stroke = Stroke {
+                    width,
+                    ..Stroke::default()
+                };
+                pixmap.stroke_path(&path, &paint, &stroke, Transform::identity(), None);
+            }
+        }
+        Ok(pixmap)
+    }
+
+    pub fn to_png(&self) -> Result<Vec<u8>, String> {
+        self.to_pixmap()?
+            .encode_png()
+            .map_err(|error| error.to_string())
+    }
+
+    pub fn to_svg(&self) -> String {
+        let (width, height) = (self.width.ceil(), self.height.ceil());
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\">\n"
+        );
+        let _ = writeln!(
+            svg,
+            "  <rect width=\"100%\" height=\"100%\" fill=\"{}\"/>",
+            self.background.hex()
+        );
+        for item in &self.items {
+            let fill = item.fill.map_or("none".to_string(), Rgb::hex);
+            let stroke = match item.stroke {
+                Some((color, width)) => {
+                    format!(" stroke=\"{}\" stroke-width=\"{width}\"", color.hex())
+                }
+                None => String::new(),
+            };
+            let _ = writeln!(
+                svg,
+                "  <path d=\"{}\" fill=\"{fill}\"{stroke}/>",
+                svg_path(&item.path)
+            );
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+/// The board drawn as in the game window, with a margin in the theme's
+/// background color.
+pub fn board_scene(board: &Board, rule: MergeRule, dark_mode: bool) -> Scene {
+    let mut scene = match board.shape() {
+        Shape::Square(size) => square_scene(board, size, rule, dark_mode),
+        Shape::Hex(radius) => hex_scene(board, radius, rule, dark_mode),
+    };
+    scene.width += 2.0 * MARGIN;
+    scene.height += 2.0 * MARGIN;
+    scene.translate(MARGIN, MARGIN)
+}
+
+/// Renders the board to `path`, as PNG or SVG depending on its extension.
+pub fn save_board(
+    board: &Board,
+    rule: MergeRule,
+    dark_mode: bool,
+    path: &Path,
+) -> Result<(), String> {
+    let scene = board_scene(board, rule, dark_mode);
+    let contents = match Format::of_path(path) {
+        Some(Format::Png) => scene.to_png()?,
+        Some(Format::Svg) => scene.to_svg().into_bytes(),
+        Some(Format::Gif) | None => {
+            return Err(format!("{}: export as .png or .svg", path.display()));
+        }
+    };
+    std::fs::write(path, contents).map_err(|error| format!("{}: {error}", path.display()))
+}
+
+/// A frame of a replay: the board, with the score line above it if asked.
+pub fn replay_frame(game: &Game, options: &ReplayOptions) -> Scene {
+    let board = board_scene(&game.board, game.variant.rule, options.dark_mode);
+    if !options.score {
+        return board;
+    }
+    let mut scene = board.translate(0.0, OVERLAY_HEIGHT);
+    scene.height += OVERLAY_HEIGHT;
+    let x = scene.width / 2.0;
+    scene.text(
+        &format!("Score {} • Move {}", game.score, game.moves),
+        x,
+        MARGIN + OVERLAY_HEIGHT / 2.0,
+        OVERLAY_TEXT,
+        palette::text(options.dark_mode),
+    );
+    scene
+}
+
+/// Animates the game in `record` to `path`, as a GIF or an APNG depending
+/// on its extension, with a frame for every move.
+pub fn save_replay(record: &Record, options: &ReplayOptions, path: &Path) -> Result<(), String> {
+    let format = Format::of_path(path)
+        .filter(|&format| format != Format::Svg)
+        .ok_or_else(|| format!("{}: export replays as .gif or .png", path.display()))?;
+    let file = File::create(path).map_err(|error| format!("{}: {error}", path.display()))?;
+    let frames = record.moves.len() + 1;
+
+    let mut animation = None;
+    // Each frame is written once the next one arrives, so the last can be
+    // held for longer
+    let mut pending: Option<Pixmap> = None;
+    record.replay_with(UndoPolicy::Disabled, options.dark_mode, |game| {
+        let pixmap = replay_frame(game, options).to_pixmap()?;
+        let animation = match &mut animation {
+            Some(animation) => animation,
+            None => animation.insert(Animation::new(
+                format,
+                BufWriter::new(&file),
+                pixmap.width(),
+                pixmap.height(),
+                frames,
+            )?),
+        };
+        if let Some(previous) = pending.replace(pixmap) {
+            animation.add(&previous, options.delay)?;
+        }
+        Ok(())
+    })?;
+    if let (Some(mut animation), Some(last)) = (animation, pending) {
+        animation.add(&last, FINAL_FRAME)?;
+        animation.finish()?;
+    }
+    Ok(())
+}
+
+/// An animation being encoded frame by frame.
+enum Animation<W: Write> {
+    Gif(gif::Encoder<W>),
+    Apng(png::Writer<W>),
+}
+
+impl<W: Write> Animation<W> {
+    fn new(
+        format: Format,
+        writer: W,
+        width: u32,
+        height: u32,
+        frames: usize,
+    ) -> Result<Self, String> {
+        let error = |error: &dyn std::fmt::Display| error.to_string();
+        match format {
+            Format::Gif => {
+                let too_large = || "the image is too large for a GIF".to_string();
+                let width = u16::try_from(width).map_err(|_| too_large())?;
+                let height = u16::try_from(height).map_err(|_| too_large())?;
+                let mut encoder =
+                    gif::Encoder::new(writer, width, height, &[]).map_err(|e| error(&e))?;
+                encoder
+                    .set_repeat(gif::Repeat::Infinite)
+                    .map_err(|e| error(&e))?;
+                Ok(Animation::Gif(encoder))
+            }
+            Format::Png | Format::Svg => {
+                let mut encoder = png::Encoder::new(writer, width, height);
+                encoder.set_color(png::ColorType::Rgba);
+                encoder.set_depth(png::BitDepth::Eight);
+                encoder
+                    .set_animated(frames as u32, 0)
+                    .map_err(|e| error(&e))?;
+                Ok(Animation::Apng(
+                    encoder.write_header().map_err(|e| error(&e))?,
+                ))
+            }
+        }
+    }
+
+    /// Adds a frame shown for `delay`. Frames are opaque, so the pixmap's
+    /// premultiplied colors are the plain ones.
+    fn add(&mut self, pixmap: &Pixmap, delay: Duration) -> Result<(), String> {
+        let millis = delay.as_millis();
+        match self {
+            Animation::Gif(encoder) => {
+                let mut rgba = pixmap.data().to_vec();
+                let mut frame = gif::Frame::from_rgba_speed(
+                    pixmap.width() as u16,
+                    pixmap.height() as u16,
+                    &mut rgba,
+                    GIF_QUANTIZER_SPEED,
+                );
+                // GIF delays are in hundredths of a second
+                frame.delay = u16::try_from(millis / 10).unwrap_or(u16::MAX);
+                encoder
+                    .write_frame(&frame)
+                    .map_err(|error| error.to_string())
+            }
+            Animation::Apng(writer) => {
+                writer
+                    .set_frame_delay(u16::try_from(millis).unwrap_or(u16::MAX), 1000)
+                    .map_err(|error| error.to_string())?;
+                writer
+                    .write_image_data(pixmap.data())
+                    .map_err(|error| error.to_string())
+            }
+        }
+    }
+
+    fn finish(self) -> Result<(), String> {
+        match self {
+            Animation::Gif(encoder) => encoder
+                .into_inner()
+                .and_then(|mut writer| writer.flush())
+                .map_err(|error| error.to_string()),
+            Animation::Apng(writer) => writer.finish().map_err(|error| error.to_string()),
+        }
+    }
+}
+
+/// Encodes straight RGBA pixels, such as a window screenshot, as PNG.
+pub fn encode_png(width: u32, height: u32, rgba: &[u8]) -> Result<Vec<u8>, String> {
+    let mut pixmap = Pixmap::new(width, height).ok_or("the image is empty")?;
+    if rgba.len() != pixmap.data().len() {
+        return Err("the pixels don't match the image size".to_string());
+    }
+    // Pixmaps hold premultiplied colors
+    for (pixel, rgba) in pixmap.pixels_mut().iter_mut().zip(rgba.chunks_exact(4)) {
+        *pixel = tiny_skia::ColorU8::from_rgba(rgba[0], rgba[1], rgba[2], rgba[3]).premultiply();
+    }
+    pixmap.encode_png().map_err(|error| error.to_string())
+}
+
+fn square_scene(board: &Board, size: usize, rule: MergeRule, dark_mode: bool) -> Scene {
+    let side = size as f32 * CELL + (size as f32 - 1.0) * GAP;
+    let mut scene = Scene::new(side, side, palette::background(dark_mode));
+    for (row, cells) in board.rows().into_iter().enumerate() {
+        for (col, cell) in cells.into_iter().enumerate() {
+            let x = col as f32 * (CELL + GAP);
+            let y = row as f32 * (CELL + GAP);
+            let (background, text) = palette::tile_colors(cell, rule, dark_mode);
+            let (width, radius) = if cell == Cell::Block {
+                (4.0, 2.0)
+            } else {
+                (2.0, 8.0)
+            };
+            // The outline is drawn inside the cell, as in the window
+            let inset = width / 2.0;
+            scene.items.push(Item {
+                path: rounded_rect(x + inset, y + inset, CELL - width, radius),
+                fill: Some(background),
+                stroke: Some((palette::border_color(cell, dark_mode), width)),
+            });
+            if let Some(value) = cell.value() {
+                let center = CELL / 2.0;
+                scene.text(&value.to_string(), x + center, y + center, TEXT_SIZE, text);
+            }
+        }
+    }
+    scene
+}
+
+fn hex_scene(board: &Board, radius: usize, rule: MergeRule, dark_mode: bool) -> Scene {
+    let size = HEX_BOARD_HEIGHT / (3f32.sqrt() * (2 * radius + 1) as f32);
+    let width = size * (3.0 * radius as f32 + 2.0);
+    let mut scene = Scene::new(width, HEX_BOARD_HEIGHT, palette::background(dark_mode));
+    let (center_x, center_y) = (width / 2.0, HEX_BOARD_HEIGHT / 2.0);
+
+    for (&(q, r), &cell) in board.shape().coordinates().iter().zip(board.cells()) {
+        let (q, r) = (q as f32, r as f32);
+        let x = center_x + size * 1.5 * q;
+        let y = center_y + size * 3f32.sqrt() * (r + q / 2.0);
+        let corner = size * HEX_CORNER;
+        let mut path: Vec<Segment> = (0..6)
+            .map(|k| {
+                let angle = std::f32::consts::FRAC_PI_3 * k as f32;
+                let (px, py) = (x + corner * angle.cos(), y + corner * angle.sin());
+                if k == 0 {
+                    Segment::MoveTo(px, py)
+                } else {
+                    Segment::LineTo(px, py)
+                }
+            })
+            .collect();
+        path.push(Segment::Close);
+
+        let (background, text) = palette::tile_colors(cell, rule, dark_mode);
+        scene.items.push(Item {
+            path,
+            fill: Some(background),
+            stroke: Some((palette::border_color(cell, dark_mode), 2.0)),
+        });
+        if let Some(value) = cell.value() {
+            scene.text(&value.to_string(), x, y, size * HEX_TEXT, text);
+        }
+    }
+    scene
+}
+
+fn rounded_rect(x: f32, y: f32, side: f32, radius: f32) -> Vec<Segment> {
+    let (left, top, right, bottom) = (x, y, x + side, y + side);
+    vec![
+        Segment::MoveTo(left + radius, top),
+        Segment::LineTo(right - radius, top),
+        Segment::QuadTo(right, top, right, top + radius),
+        Segment::LineTo(right, bottom - radius),
+        Segment::QuadTo(right, bottom, right - radius, bottom),
+        Segment::LineTo(left + radius, bottom),
+        Segment::QuadTo(left, bottom, left, bottom - radius),
+        Segment::LineTo(left, top + radius),
+        Segment::QuadTo(left, top, left + radius, top),
+        Segment::Close,
+    ]
+}
+
+fn font() -> &'static ttf_parser::Face<'static> {
+    static FACE: OnceLock<ttf_parser::Face<'static>> = OnceLock::new();
+    FACE.get_or_init(|| ttf_parser::Face::parse(FONT, 0).expect("the bundled font is valid"))
+}
+
+/// Outlines of `text` at `size` pixels, centred on `(x, y)`. Lines are
+/// centred on the height of a capital letter, which digits share.
+fn text_path(text: &str, x: f32, y: f32, size: f32) -> Vec<Segment> {
+    let face = font();
+    let scale = size / f32::from(face.units_per_em());
+    let glyphs: Vec<_> = text.chars().filter_map(|c| face.glyph_index(c)).collect();
+    let width: f32 = glyphs
+        .iter()
+        .map(|&glyph| f32::from(face.glyph_hor_advance(glyph).unwrap_or(0)))
+        .sum::<f32>()
+        * scale;
+    let cap_height = f32::from(face.capital_height().unwrap_or(face.ascender())) * scale;
+
+    let mut outline = Outline {
+        segments: Vec::new(),
+        x: x - width / 2.0,
+        y: y + cap_height / 2.0,
+        scale,
+    };
+    for glyph in glyphs {
+        face.outline_glyph(glyph, &mut outline);
+        outline.x += f32::from(face.glyph_hor_advance(glyph).unwrap_or(0)) * scale;
+    }
+    outline.segments
+}
+
+/// Collects glyph outlines in image coordinates: scaled, with y pointing
+/// down, from the pen position `(x, y)` on the baseline.
+struct Outline {
+    segments: Vec<Segment>,
+    x: f32,
+    y: f32,
+    scale: f32,
+}
+
+impl Outline {
+    fn point(&self, x: f32, y: f32) -> (f32, f32) {
+        (self.x + x * self.scale, self.y - y * self.scale)
+    }
+}
+
+impl ttf_parser::OutlineBuilder for Outline {
+    fn move_to(&mut self, x: f32, y: f32) {
+        let (x, y) = self.point(x, y);
+        self.segments.push(Segment::MoveTo(x, y));
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let (x, y) = self.point(x, y);
+        self.segments.push(Segment::LineTo(x, y));
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let (x1, y1) = self.point(x1, y1);
+        let (x, y) = self.point(x, y);
+        self.segments.push(Segment::QuadTo(x1, y1, x, y));
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let (x1, y1) = self.point(x1, y1);
+        let (x2, y2) = self.point(x2, y2);
+        let (x, y) = self.point(x, y);
+        self.segments.push(Segment::CubicTo(x1, y1, x2, y2, x, y));
+    }
+
+    fn close(&mut self) {
+        self.segments.push(Segment::Close);
+    }
+}
+
+fn skia_color(color: Rgb) -> tiny_skia::Color {
+    tiny_skia::Color::from_rgba(color.r, color.g, color.b, 1.0).unwrap_or(tiny_skia::Color::BLACK)
+}
+
+fn skia_path(segments: &[Segment]) -> Option<tiny_skia::Path> {
+    let mut builder = PathBuilder::new();
+    for &segment in segments {
+        match segment {
+            Segment::MoveTo(x, y) => builder.move_to(x, y),
+            Segment::LineTo(x, y) => builder.line_to(x, y),
+            Segment::QuadTo(x1, y1, x, y) => builder.quad_to(x1, y1, x, y),
+            Segment::CubicTo(x1, y1, x2, y2, x, y) => builder.cubic_to(x1, y1, x2, y2, x, y),
+            Segment::Close => builder.close(),
+        }
+    }
+    builder.finish()
+}
+
+fn svg_path(segments: &[Segment]) -> String {
+    segments
+        .iter()
+        .map(|segment| match *segment {
+            Segment::MoveTo(x, y) => format!("M{x:.2} {y:.2}"),
+            Segment::LineTo(x, y) => format!("L{x:.2} {y:.2}"),
+            Segment::QuadTo(x1, y1, x, y) => format!("Q{x1:.2} {y1:.2} {x:.2} {y:.2}"),
+            Segment::CubicTo(x1, y1, x2, y2, x, y) => {
+                format!("C{x1:.2} {y1:.2} {x2:.2} {y2:.2} {x:.2} {y:.2}")
+            }
+            Segment::Close => "Z".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("")
+}
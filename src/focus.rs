@@ -0,0 +1,93 @@
+//! Keyboard focus for buttons, which iced only lets the mouse press.
+//!
+//! Views make their buttons [`focusable`], which lines them up in the order
+//! they're drawn. Tab and Shift+Tab move the focus along that chain, Enter
+//! presses the focused button, and a ring shows which one it is.
+
+use crate::Message;
+use iced::widget::{Button, container};
+use iced::{Border, Color, Element};
+use std::cell::RefCell;
+
+thread_local! {
+    /// The buttons of the view drawn last. Views and updates both run on
+    /// the UI thread, and views can't keep state of their own.
+    static CHAIN: RefCell<Chain> = const {
+        RefCell::new(Chain {
+            focused: None,
+            messages: Vec::new(),
+        })
+    };
+}
+
+struct Chain {
+    focused: Option<usize>,
+    /// What each button sends when pressed, in order.
+    messages: Vec<Message>,
+}
+
+/// Starts a new chain for a view about to be drawn, ringing its `focused`th
+/// button.
+pub fn begin(focused: Option<usize>) {
+    CHAIN.with_borrow_mut(|chain| {
+        chain.focused = focused;
+        chain.messages.clear();
+    });
+}
+
+/// Makes `button` send `message` when clicked or pressed from the keyboard.
+pub fn focusable<'a>(button: Button<'a, Message>, message: Message) -> Element<'a, Message> {
+    let focused = CHAIN.with_borrow_mut(|chain| {
+        chain.messages.push(message.clone());
+        chain.focused == Some(chain.messages.len() - 1)
+    });
+    // Every button keeps room for the ring, so focus doesn't move anything
+    container(button.on_press(message))
+        .padding(3)
+        .style(move |theme: &iced::Theme| container::Style {
+            border: Border {
+                color: if focused {
+                    theme.palette().primary
+                } else {
+                    Color::TRANSPARENT
+                },
+                width: 2.0,
+                radius: 6.0.into(),
+            },
+            ..container::Style::default()
+        })
+        .into()
+}
+
+/// Like [`focusable`], but leaves the button disabled, and out of the
+/// chain, without a message.
+pub fn focusable_maybe<'a>(
+    button: Button<'a, Message>,
+    message: Option<Message>,
+) -> Element<'a, Message> {
+    match message {
+        Some(message) => focusable(button, message),
+        // Padded like the others, so the two line up
+        None => container(button).padding(3).into(),
+    }
+}
+
+/// Where focus goes from `focused` on a step forward or back, wrapping
+/// around the ends. `None` if the view has no buttons.
+pub fn step(focused: Option<usize>, forward: bool) -> Option<usize> {
+    let count = CHAIN.with_borrow(|chain| chain.messages.len());
+    if count == 0 {
+        return None;
+    }
+    Some(match (focused, forward) {
+        (Some(i), true) => (i + 1) % count,
+        (Some(i), false) => (i + count - 1) % count,
+        (None, true) => 0,
+        (None, false) => count - 1,
+    })
+}
+
+/// What the `index`th button of the last view sends.
+pub fn message(index: usize) -> Option<Message> {
+    CHAIN.with_borrow(|chain| chain.messages.get(index).cloned())
+}
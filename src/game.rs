@@ -0,0 +1,942 @@
+//! The rules of the game, independent of how it's displayed.
+
+use crate::board::{Board, Shape};
+use crate::data_file_path;
+use crate::date::Date;
+use crate::protocol::PlayerState;
+use crate::puzzle::{Objective, Spawn, Status};
+use crate::rng::SpawnRng;
+use crate::rules::MergeRule;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// Where the game is saved when it isn't `save.json` in the data
+/// directory; `None` when saving is off. Set once, from the command line.
+static SAVE_FILE: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+    /// The four diagonals only exist on hex boards.
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+impl Direction {
+    /// Parses the snake_case names used by the control API.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "up" => Some(Direction::Up),
+            "down" => Some(Direction::Down),
+            "left" => Some(Direction::Left),
+            "right" => Some(Direction::Right),
+            "up_left" => Some(Direction::UpLeft),
+            "up_right" => Some(Direction::UpRight),
+            "down_left" => Some(Direction::DownLeft),
+            "down_right" => Some(Direction::DownRight),
+            _ => None,
+        }
+    }
+
+    /// The letter for the direction in move lists: U, D, L and R, with
+    /// Q, E, Z and C for the diagonals, after the corners of a keyboard.
+    pub fn letter(self) -> char {
+        match self {
+            Direction::Up => 'U',
+            Direction::Down => 'D',
+            Direction::Left => 'L',
+            Direction::Right => 'R',
+            Direction::UpLeft => 'Q',
+            Direction::UpRight => 'E',
+            Direction::DownLeft => 'Z',
+            Direction::DownRight => 'C',
+        }
+    }
+
+    pub fn from_letter(letter: char) -> Option<Self> {
+        [
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+            Direction::UpLeft,
+            Direction::UpRight,
+            Direction::DownLeft,
+            Direction::DownRight,
+        ]
+        .into_iter()
+        .find(|direction| direction.letter() == letter.to_ascii_uppercase())
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Direction::Up => "up",
+            Direction::Down => "down",
+            Direction::Left => "left",
+            Direction::Right => "right",
+            Direction::UpLeft => "up-left",
+            Direction::UpRight => "up-right",
+            Direction::DownLeft => "down-left",
+            Direction::DownRight => "down-right",
+        }
+    }
+}
+
+/// Contents of a board cell. Saved as a number: 0 for empty, -1 for a
+/// block, otherwise the tile value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(into = "i64", try_from = "i64")]
+pub enum Cell {
+    #[default]
+    Empty,
+    Tile(u32),
+    /// Immovable obstacle that tiles can't pass through or merge with.
+    Block,
+}
+
+impl Cell {
+    pub fn value(self) -> Option<u32> {
+        match self {
+            Cell::Tile(value) => Some(value),
+            Cell::Empty | Cell::Block => None,
+        }
+    }
+}
+
+impl From<Cell> for i64 {
+    fn from(cell: Cell) -> Self {
+        match cell {
+            Cell::Empty => 0,
+            Cell::Tile(value) => i64::from(value),
+            Cell::Block => -1,
+        }
+    }
+}
+
+impl TryFrom<i64> for Cell {
+    type Error = String;
+
+    fn try_from(value: i64) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Cell::Empty),
+            -1 => Ok(Cell::Block),
+            _ => u32::try_from(value)
+                .map(Cell::Tile)
+                .map_err(|_| format!("invalid cell: {value}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Mode {
+    #[default]
+    Classic,
+    /// One attempt per day, with spawns seeded from the date.
+    Daily(Date),
+    /// Highest score before the clock runs out.
+    TimeAttack { seconds: u32 },
+    /// Best score within a fixed number of moves.
+    LimitedMoves { moves: u32 },
+    /// A hand-made position to solve; see [`crate::puzzle`].
+    Puzzle(Objective),
+}
+
+/// How undo behaves in a game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum UndoPolicy {
+    #[default]
+    Unlimited,
+    /// A fixed budget of undos for the whole game.
+    Limited(u32),
+    /// Every undo costs this many points.
+    Penalty(u32),
+    Disabled,
+}
+
+pub const UNDO_POLICIES: [UndoPolicy; 5] = [
+    UndoPolicy::Unlimited,
+    UndoPolicy::Limited(3),
+    UndoPolicy::Limited(10),
+    UndoPolicy::Penalty(100),
+    UndoPolicy::Disabled,
+];
+
+impl UndoPolicy {
+    pub fn label(self) -> String {
+        match self {
+            UndoPolicy::Unlimited => "Unlimited undo".to_string(),
+            UndoPolicy::Limited(1) => "1 undo".to_string(),
+            UndoPolicy::Limited(undos) => format!("{undos} undos"),
+            UndoPolicy::Penalty(points) => format!("Undo costs {points}"),
+            UndoPolicy::Disabled => "No undo".to_string(),
+        }
+    }
+
+    /// Suffix keeping classic games with this policy on a leaderboard table
+    /// of their own. Unlimited undo has none, as it was the only table
+    /// before.
+    pub fn key_suffix(self) -> String {
+        match self {
+            UndoPolicy::Unlimited => String::new(),
+            UndoPolicy::Limited(undos) => format!("-undo-{undos}"),
+            UndoPolicy::Penalty(points) => format!("-undo-penalty-{points}"),
+            UndoPolicy::Disabled => "-no-undo".to_string(),
+        }
+    }
+}
+
+/// Immovable blocks on the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Obstacles {
+    #[default]
+    Off,
+    /// Blocks placed when the game starts.
+    Fixed(u32),
+    /// One block at the start and another every `interval` moves.
+    Growing { interval: u32 },
+}
+
+pub const OBSTACLES: [Obstacles; 4] = [
+    Obstacles::Off,
+    Obstacles::Fixed(1),
+    Obstacles::Fixed(2),
+    Obstacles::Growing { interval: 50 },
+];
+
+/// Growing obstacles stop appearing once this many blocks are on the board.
+const MAX_GROWING_BLOCKS: usize = 4;
+
+impl Obstacles {
+    pub fn label(self) -> String {
+        match self {
+            Obstacles::Off => "No blocks".to_string(),
+            Obstacles::Fixed(1) => "1 block".to_string(),
+            Obstacles::Fixed(blocks) => format!("{blocks} blocks"),
+            Obstacles::Growing { interval } => format!("Block every {interval} moves"),
+        }
+    }
+}
+
+/// Rule changes that get their own high-score tables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Variant {
+    #[serde(default)]
+    pub obstacles: Obstacles,
+    #[serde(default)]
+    pub rule: MergeRule,
+    #[serde(default)]
+    pub shape: Shape,
+}
+
+pub const SHAPES: [Shape; 3] = [Shape::Square(4), Shape::Hex(2), Shape::Hex(3)];
+
+impl Variant {
+    pub fn label(self) -> String {
+        let mut parts = Vec::new();
+        if self.shape != Shape::default() {
+            parts.push(self.shape.label());
+        }
+        if self.rule != MergeRule::Standard {
+            parts.push(self.rule.label());
+        }
+        if self.obstacles != Obstacles::Off {
+            parts.push(self.obstacles.label());
+        }
+        if parts.is_empty() {
+            "Standard".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+
+    /// Suffix distinguishing this variant's leaderboard tables. The standard
+    /// game has none, which keeps older tables valid.
+    fn key_suffix(self) -> String {
+        let rule = match self.rule {
+            MergeRule::Standard => "",
+            MergeRule::Fibonacci => "-fibonacci",
+            MergeRule::Threes => "-threes",
+            MergeRule::Base3 => "-base3",
+        };
+        let obstacles = match self.obstacles {
+            Obstacles::Off => String::new(),
+            Obstacles::Fixed(blocks) => format!("-blocks-{blocks}"),
+            Obstacles::Growing { interval } => format!("-growing-blocks-{interval}"),
+        };
+        let shape = match self.shape {
+            Shape::Square(4) => String::new(),
+            Shape::Square(size) => format!("-square-{size}"),
+            Shape::Hex(radius) => format!("-hex-{radius}"),
+        };
+        format!("{shape}{rule}{obstacles}")
+    }
+}
+
+pub const TIME_ATTACK_MINUTES: [u32; 3] = [1, 3, 5];
+pub const MOVE_LIMITS: [u32; 3] = [50, 100, 200];
+
+impl Mode {
+    pub fn label(self) -> String {
+        match self {
+            Mode::Classic => "Classic".to_string(),
+            Mode::Daily(date) => format!("Daily Challenge {date}"),
+            Mode::TimeAttack { seconds } => format!("Time Attack {} min", seconds / 60),
+            Mode::LimitedMoves { moves } => format!("Limited Moves {moves}"),
+            Mode::Puzzle(objective) => format!("Puzzle: {}", objective.label()),
+        }
+    }
+
+    /// Ranked modes keep their own high-score table.
+    pub fn is_ranked(self) -> bool {
+        matches!(self, Mode::TimeAttack { .. } | Mode::LimitedMoves { .. })
+    }
+
+    /// The leaderboard table for games of this mode, variant and undo
+    /// policy. Only classic games can be played with undo, so the policy
+    /// only splits their tables.
+    pub fn leaderboard_key(self, variant: Variant, undo_policy: UndoPolicy) -> String {
+        let undo = match self {
+            Mode::Classic => undo_policy.key_suffix(),
+            _ => String::new(),
+        };
+        let mode = match self {
+            Mode::Classic => "classic".to_string(),
+            Mode::Daily(date) => format!("daily-{date}"),
+            Mode::TimeAttack { seconds } => format!("time-attack-{seconds}"),
+            Mode::LimitedMoves { moves } => format!("limited-moves-{moves}"),
+            Mode::Puzzle(_) => "puzzle".to_string(),
+        };
+        mode + &variant.key_suffix() + &undo
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Game {
+    pub board: Board,
+    pub score: u32,
+    pub game_over: bool,
+    pub won: bool,
+    pub dark_mode: bool,
+    history: Vec<GameState>,
+    #[serde(default)]
+    pub mode: Mode,
+    /// Spawn source for seeded games; unseeded games use the thread RNG.
+    #[serde(default)]
+    pub rng: Option<SpawnRng>,
+    #[serde(default)]
+    pub moves: u32,
+    #[serde(default)]
+    pub elapsed_secs: u32,
+    /// Whether a finished game has been entered on the leaderboard.
+    #[serde(default)]
+    pub recorded: bool,
+    #[serde(default)]
+    pub undo_policy: UndoPolicy,
+    #[serde(default)]
+    pub undos_used: u32,
+    #[serde(default)]
+    pub variant: Variant,
+    /// Tiles still to be placed by a puzzle, in order.
+    #[serde(default)]
+    pub spawns: VecDeque<Spawn>,
+    /// Key of the puzzle being played, from [`crate::puzzle::key`].
+    #[serde(default)]
+    pub puzzle: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct GameState {
+    board: Board,
+    score: u32,
+    game_over: bool,
+    won: bool,
+    #[serde(default)]
+    rng: Option<SpawnRng>,
+    #[serde(default)]
+    moves: u32,
+    /// The move played from this state. Missing from older saves.
+    #[serde(default)]
+    direction: Option<Direction>,
+}
+
+/// A move of a game as it was played.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Turn {
+    /// The board the move was played on, after the previous spawn.
+    pub board: Board,
+    pub score: u32,
+    /// Moves played before this one.
+    pub moves: u32,
+    pub direction: Direction,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Game {
+    pub fn new() -> Self {
+        let mut game = Game {
+            board: Board::new(Shape::default()),
+            score: 0,
+            game_over: false,
+            won: false,
+            dark_mode: false,
+            history: Vec::new(),
+            mode: Mode::Classic,
+            rng: None,
+            moves: 0,
+            elapsed_secs: 0,
+            recorded: false,
+            undo_policy: UndoPolicy::Unlimited,
+            undos_used: 0,
+            variant: Variant::default(),
+            spawns: VecDeque::new(),
+            puzzle: None,
+        };
+        game.add_random_tile();
+        game.add_random_tile();
+        game
+    }
+
+    pub fn new_with_theme(dark_mode: bool) -> Self {
+        Self::new_with_mode(
+            Mode::Classic,
+            UndoPolicy::Unlimited,
+            Variant::default(),
+            dark_mode,
+        )
+    }
+
+    pub fn new_with_mode(
+        mode: Mode,
+        undo_policy: UndoPolicy,
+        variant: Variant,
+        dark_mode: bool,
+    ) -> Self {
+        let rng = match mode {
+            Mode::Daily(date) => Some(SpawnRng::new(date.seed())),
+            _ => None,
+        };
+        // Everyone plays the same daily board
+        let variant = match mode {
+            Mode::Daily(_) => Variant::default(),
+            _ => variant,
+        };
+        // Daily challenges and ranked modes are a single attempt
+        let undo_policy = match mode {
+            Mode::Classic => undo_policy,
+            _ => UndoPolicy::Disabled,
+        };
+        Self::build(mode, undo_policy, variant, dark_mode, rng)
+    }
+
+    /// A game without undo whose spawns come from `seed`, so that every game
+    /// sharing the seed faces the same spawns.
+    pub fn new_seeded(seed: u64, variant: Variant, dark_mode: bool) -> Self {
+        Self::build(
+            Mode::Classic,
+            UndoPolicy::Disabled,
+            variant,
+            dark_mode,
+            Some(SpawnRng::new(seed)),
+        )
+    }
+
+    pub fn build(
+        mode: Mode,
+        undo_policy: UndoPolicy,
+        variant: Variant,
+        dark_mode: bool,
+        rng: Option<SpawnRng>,
+    ) -> Self {
+        let mut game = Game {
+            board: Board::new(variant.shape),
+            score: 0,
+            game_over: false,
+            won: false,
+            dark_mode,
+            history: Vec::new(),
+            mode,
+            rng,
+            moves: 0,
+            elapsed_secs: 0,
+            recorded: false,
+            undo_policy,
+            undos_used: 0,
+            variant,
+            spawns: VecDeque::new(),
+            puzzle: None,
+        };
+        game.setup_board();
+        game
+    }
+
+    /// A classic game starting from a hand-made position, such as one from
+    /// the board editor. It's kept off the leaderboard, since the position
+    /// could be anything. Fails if the board has no tiles, has a tile the
+    /// rule can't make, or has no legal move.
+    pub fn from_position(
+        board: Board,
+        rule: MergeRule,
+        undo_policy: UndoPolicy,
+        dark_mode: bool,
+    ) -> Result<Self, String> {
+        let tiles: Vec<u32> = board
+            .cells()
+            .iter()
+            .filter_map(|cell| cell.value())
+            .collect();
+        if tiles.is_empty() {
+            return Err("the board has no tiles".to_string());
+        }
+        if let Some(value) = tiles.iter().find(|&&value| !rule.is_tile(value)) {
+            return Err(format!("{value} isn't a tile in {}", rule.label()));
+        }
+        let variant = Variant {
+            rule,
+            shape: board.shape(),
+            ..Variant::default()
+        };
+        let mut game = Self::build(Mode::Classic, undo_policy, variant, dark_mode, None);
+        game.board = board;
+        game.recorded = true;
+        let stuck = game
+            .board
+            .shape()
+            .directions()
+            .iter()
+            .all(|&direction| game.slide(direction).0 == game.board);
+        if stuck {
+            return Err("no tile can move".to_string());
+        }
+        Ok(game)
+    }
+
+    /// A copy to try moves on without touching this game: it has no undo
+    /// history, and its spawns come from `seed`.
+    pub fn playout(&self, seed: u64) -> Game {
+        Game {
+            board: self.board.clone(),
+            history: Vec::new(),
+            spawns: self.spawns.clone(),
+            puzzle: self.puzzle.clone(),
+            rng: Some(SpawnRng::new(seed)),
+            undo_policy: UndoPolicy::Disabled,
+            ..*self
+        }
+    }
+
+    /// Picks an empty cell, using the game's seeded RNG when it has one.
+    fn random_empty_cell(&mut self) -> Option<usize> {
+        let empty_cells: Vec<usize> = (0..self.board.cells().len())
+            .filter(|&i| self.board[i] == Cell::Empty)
+            .collect();
+
+        if empty_cells.is_empty() {
+            return None;
+        }
+        let index = match &mut self.rng {
+            Some(rng) => rng.below(empty_cells.len()),
+            None => rand::rng().random_range(0..empty_cells.len()),
+        };
+        Some(empty_cells[index])
+    }
+
+    fn add_random_tile(&mut self) {
+        // Puzzles place their own tiles, skipping any whose cell is taken
+        if let Mode::Puzzle(_) = self.mode {
+            if let Some(spawn) = self.spawns.pop_front()
+                && self.board[spawn.cell] == Cell::Empty
+            {
+                self.board[spawn.cell] = Cell::Tile(spawn.value);
+            }
+            return;
+        }
+        if let Some(i) = self.random_empty_cell() {
+            let value = match &mut self.rng {
+                Some(rng) => {
+                    let low = rng.chance(0.9);
+                    self.variant.rule.spawn(low, || rng.chance(0.5))
+                }
+                None => {
+                    let mut rng = rand::rng();
+                    let low = rng.random_bool(0.9);
+                    self.variant.rule.spawn(low, || rng.random_bool(0.5))
+                }
+            };
+            self.board[i] = Cell::Tile(value);
+        }
+    }
+
+    fn add_block(&mut self) {
+        if let Some(i) = self.random_empty_cell() {
+            self.board[i] = Cell::Block;
+        }
+    }
+
+    fn block_count(&self) -> usize {
+        self.board
+            .cells()
+            .iter()
+            .filter(|&&cell| cell == Cell::Block)
+            .count()
+    }
+
+    /// Clears the board and places the starting blocks and tiles.
+    fn setup_board(&mut self) {
+        self.board = Board::new(self.variant.shape);
+        let blocks = match self.variant.obstacles {
+            Obstacles::Off => 0,
+            Obstacles::Fixed(blocks) => blocks,
+            Obstacles::Growing { .. } => 1,
+        };
+        for _ in 0..blocks {
+            self.add_block();
+        }
+        self.add_random_tile();
+        self.add_random_tile();
+    }
+
+    pub fn move_tiles(&mut self, direction: Direction) -> bool {
+        self.play(direction, |game| {
+            game.add_random_tile();
+            if let Obstacles::Growing { interval } = game.variant.obstacles
+                && game.moves.is_multiple_of(interval)
+                && game.block_count() < MAX_GROWING_BLOCKS
+            {
+                game.add_block();
+            }
+        })
+    }
+
+    /// Moves like [`Game::move_tiles`], but places the given tiles and
+    /// blocks instead of random ones, as when replaying a recorded game.
+    /// Cells that are already taken are skipped.
+    pub fn move_placing(&mut self, direction: Direction, placed: &[(usize, Cell)]) -> bool {
+        self.play(direction, |game| {
+            for &(i, cell) in placed {
+                if i < game.board.cells().len() && game.board[i] == Cell::Empty {
+                    game.board[i] = cell;
+                }
+            }
+        })
+    }
+
+    /// Plays a move, with `spawn` adding the new tiles once the board has
+    /// slid.
+    fn play(&mut self, direction: Direction, spawn: impl FnOnce(&mut Self)) -> bool {
+        let (board, merges) = self.slide(direction);
+        if board == self.board {
+            return false;
+        }
+
+        // Save current state before making a move
+        self.save_state(direction);
+        self.board = board;
+        self.score += merges.iter().sum::<u32>();
+        if merges
+            .iter()
+            .any(|&value| value >= self.variant.rule.win_target())
+        {
+            self.won = true;
+        }
+
+        self.moves += 1;
+        spawn(self);
+        self.check_game_state();
+        if let Mode::LimitedMoves { moves } = self.mode
+            && self.moves >= moves
+        {
+            self.game_over = true;
+        }
+        if let Mode::Puzzle(objective) = self.mode {
+            // Only solving the puzzle counts as a win
+            let status = objective.status(self);
+            self.won = status == Status::Solved;
+            self.game_over = status != Status::Open;
+        }
+        true
+    }
+
+    /// The board after sliding in `direction`, before any tile spawns, and
+    /// the value of every tile created by a merge. Leaves the game as is.
+    pub fn slide(&self, direction: Direction) -> (Board, Vec<u32>) {
+        let mut board = self.board.clone();
+        let mut merges = Vec::new();
+        for positions in board.lines(direction) {
+            let mut line: Vec<Cell> = positions.iter().map(|&i| board[i]).collect();
+            Self::slide_line(self.variant.rule, &mut line, &mut merges);
+            for (&i, cell) in positions.iter().zip(line) {
+                board[i] = cell;
+            }
+        }
+        (board, merges)
+    }
+
+    /// Slides a line towards its first cell, recording merged values in
+    /// `merges`. Blocks split the line into segments that slide independently.
+    fn slide_line(rule: MergeRule, line: &mut [Cell], merges: &mut Vec<u32>) {
+        for segment in line.split_mut(|&cell| cell == Cell::Block) {
+            // Move all tiles to the front
+            let tiles: Vec<u32> = segment.iter().filter_map(|cell| cell.value()).collect();
+
+            // Merge adjacent tiles, each tile at most once
+            let mut merged = Vec::with_capacity(tiles.len());
+            let mut i = 0;
+            while i < tiles.len() {
+                if let Some((value, consumed)) = rule.merge(&tiles[i..]) {
+                    merges.push(value);
+                    merged.push(value);
+                    i += consumed;
+                } else {
+                    merged.push(tiles[i]);
+                    i += 1;
+                }
+            }
+
+            for (k, cell) in segment.iter_mut().enumerate() {
+                *cell = merged
+                    .get(k)
+                    .map_or(Cell::Empty, |&value| Cell::Tile(value));
+            }
+        }
+    }
+
+    fn check_game_state(&mut self) {
+        // Check if there are any empty cells
+        if self.board.cells().contains(&Cell::Empty) {
+            return; // Game continues
+        }
+
+        // Check if any line can merge in any direction; blocks never merge
+        for &direction in self.board.shape().directions() {
+            for positions in self.board.lines(direction) {
+                let line: Vec<Cell> = positions.iter().map(|&i| self.board[i]).collect();
+                for segment in line.split(|&cell| cell == Cell::Block) {
+                    let tiles: Vec<u32> = segment.iter().filter_map(|cell| cell.value()).collect();
+                    if (0..tiles.len()).any(|i| self.variant.rule.merge(&tiles[i..]).is_some()) {
+                        return; // Game continues
+                    }
+                }
+            }
+        }
+
+        self.game_over = true;
+    }
+
+    fn save_state(&mut self, direction: Direction) {
+        let state = GameState {
+            board: self.board.clone(),
+            score: self.score,
+            game_over: self.game_over,
+            won: self.won,
+            rng: self.rng,
+            moves: self.moves,
+            direction: Some(direction),
+        };
+        self.history.push(state);
+    }
+
+    pub fn undo(&mut self) -> bool {
+        if !self.can_undo() {
+            return false;
+        }
+        if let Some(previous_state) = self.history.pop() {
+            self.board = previous_state.board;
+            self.score = previous_state.score;
+            self.game_over = previous_state.game_over;
+            self.won = previous_state.won;
+            self.rng = previous_state.rng;
+            self.moves = previous_state.moves;
+            self.undos_used += 1;
+            if let UndoPolicy::Penalty(points) = self.undo_policy {
+                // Charge every earlier state too, so that further undos
+                // don't hand the points back
+                self.score = self.score.saturating_sub(points);
+                for state in &mut self.history {
+                    state.score = state.score.saturating_sub(points);
+                }
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        // Daily challenges and ranked modes are a single attempt, so undo is disabled
+        !self.history.is_empty() && self.mode == Mode::Classic && self.undos_left() != Some(0)
+    }
+
+    /// Undos remaining under the game's policy, or `None` if unbounded.
+    pub fn undos_left(&self) -> Option<u32> {
+        match self.undo_policy {
+            UndoPolicy::Unlimited | UndoPolicy::Penalty(_) => None,
+            UndoPolicy::Limited(undos) => Some(undos.saturating_sub(self.undos_used)),
+            UndoPolicy::Disabled => Some(0),
+        }
+    }
+
+    pub fn undo_label(&self) -> String {
+        match (self.undo_policy, self.undos_left()) {
+            (UndoPolicy::Penalty(points), _) => format!("Undo (-{points})"),
+            (UndoPolicy::Unlimited, _) | (_, None) => "Undo".to_string(),
+            (_, Some(left)) => format!("Undo ({left} left)"),
+        }
+    }
+
+    /// Advances the time attack clock by one second.
+    pub fn tick(&mut self) {
+        if let Mode::TimeAttack { seconds } = self.mode
+            && !self.game_over
+        {
+            self.elapsed_secs += 1;
+            if self.elapsed_secs >= seconds {
+                self.game_over = true;
+            }
+        }
+    }
+
+    /// What's left of the mode's time or move budget, for display.
+    pub fn remaining(&self) -> Option<String> {
+        match self.mode {
+            Mode::TimeAttack { seconds } => {
+                let left = seconds.saturating_sub(self.elapsed_secs);
+                Some(format!("{}:{:02} left", left / 60, left % 60))
+            }
+            Mode::LimitedMoves { moves } => {
+                Some(format!("{} moves left", moves.saturating_sub(self.moves)))
+            }
+            Mode::Puzzle(objective) => Some(format!(
+                "{} moves left",
+                objective.moves().saturating_sub(self.moves)
+            )),
+            Mode::Classic | Mode::Daily(_) => None,
+        }
+    }
+
+    pub fn max_tile(&self) -> u32 {
+        self.board
+            .cells()
+            .iter()
+            .filter_map(|cell| cell.value())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The moves of the current game in order, without any that were undone.
+    /// Empty if the game was saved before moves were recorded.
+    pub fn turns(&self) -> Vec<Turn> {
+        // Older saves kept the history through a reset, so the game starts
+        // at its last move 0, unless no move has been played since
+        let start = self
+            .history
+            .iter()
+            .rposition(|state| state.moves == 0)
+            .unwrap_or(self.history.len());
+        if self.history.len() - start != self.moves as usize {
+            return Vec::new();
+        }
+        self.history[start..]
+            .iter()
+            .map(|state| {
+                Some(Turn {
+                    board: state.board.clone(),
+                    score: state.score,
+                    moves: state.moves,
+                    direction: state.direction?,
+                })
+            })
+            .collect::<Option<_>>()
+            .unwrap_or_default()
+    }
+
+    /// The game as sent to race opponents and spectators.
+    pub fn player_state(&self) -> PlayerState {
+        PlayerState {
+            score: self.score,
+            moves: self.moves,
+            board: self
+                .board
+                .rows()
+                .into_iter()
+                .map(|cells| cells.into_iter().map(i64::from).collect())
+                .collect(),
+            game_over: self.game_over,
+            won: self.won,
+            last_move: self.last_move(),
+        }
+    }
+
+    /// The direction of the move that led to this position, unless it's the
+    /// start of the game.
+    pub fn last_move(&self) -> Option<Direction> {
+        if self.moves == 0 {
+            return None;
+        }
+        self.history.last()?.direction
+    }
+
+    pub fn reset_tiles(&mut self) {
+        // Daily challenges and ranked modes can't be restarted
+        if self.mode != Mode::Classic {
+            return;
+        }
+
+        // Start over as a New Game does, keeping only the settings: undos
+        // belong to the game they were spent in
+        self.score = 0;
+        self.game_over = false;
+        self.won = false;
+        self.moves = 0;
+        self.recorded = false;
+        self.undos_used = 0;
+        self.history.clear();
+        self.setup_board();
+    }
+
+    /// Saves to and loads from `path` for the rest of the run instead of
+    /// the data directory, or neither with `None`. Only the first call
+    /// counts.
+    pub fn set_save_file(path: Option<PathBuf>) {
+        let _ = SAVE_FILE.set(path);
+    }
+
+    fn save_file_path() -> Option<PathBuf> {
+        match SAVE_FILE.get() {
+            Some(path) => path.clone(),
+            None => data_file_path("save.json"),
+        }
+    }
+
+    pub fn save(&self) {
+        if let Some(path) = Self::save_file_path()
+            && let Ok(json) = serde_json::to_string_pretty(self)
+        {
+            let _ = fs::write(path, json);
+        }
+    }
+
+    pub fn load() -> Option<Self> {
+        if let Some(path) = Self::save_file_path()
+            && let Ok(contents) = fs::read_to_string(path)
+            && let Ok(game) = serde_json::from_str::<Game>(&contents)
+        {
+            return Some(game);
+        }
+        None
+    }
+}
@@ -0,0 +1,116 @@
+use crate::{Message, tile_border_color, tile_colors};
+use iced::alignment;
+use iced::mouse;
+use iced::widget::canvas::{self, Frame, Geometry, Path, Stroke, Text};
+use iced::{Element, Pixels, Point, Rectangle, Renderer, Theme};
+use iced_2048::board::{Board, Shape};
+use iced_2048::rules::MergeRule;
+
+/// Height of the rendered board, whatever its radius.
+const BOARD_HEIGHT: f32 = 420.0;
+
+/// Draws a hex board on a canvas, with flat-topped cells.
+struct HexBoard<'a> {
+    board: &'a Board,
+    rule: MergeRule,
+    dark_mode: bool,
+    opacity: f32,
+}
+
+pub fn view_board(
+    board: &Board,
+    rule: MergeRule,
+    dark_mode: bool,
+    opacity: f32,
+) -> Element<'_, Message> {
+    let Shape::Hex(radius) = board.shape() else {
+        unreachable!("hex renderer used for a {} board", board.shape().label());
+    };
+    let size = cell_size(radius);
+    let width = size * (3.0 * radius as f32 + 2.0);
+
+    canvas::Canvas::new(HexBoard {
+        board,
+        rule,
+        dark_mode,
+        opacity,
+    })
+    .width(width)
+    .height(BOARD_HEIGHT)
+    .into()
+}
+
+/// Distance from a cell's centre to its corners.
+fn cell_size(radius: usize) -> f32 {
+    BOARD_HEIGHT / (3f32.sqrt() * (2 * radius + 1) as f32)
+}
+
+impl canvas::Program<Message> for HexBoard<'_> {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let Shape::Hex(radius) = self.board.shape() else {
+            return Vec::new();
+        };
+        let size = cell_size(radius);
+        let center = Point::new(bounds.width / 2.0, bounds.height / 2.0);
+        let mut frame = Frame::new(renderer, bounds.size());
+
+        for ((q, r), &cell) in self
+            .board
+            .shape()
+            .coordinates()
+            .iter()
+            .zip(self.board.cells())
+        {
+            let (q, r) = (*q as f32, *r as f32);
+            let x = center.x + size * 1.5 * q;
+            let y = center.y + size * 3f32.sqrt() * (r + q / 2.0);
+
+            // Leave a small gap between neighbouring cells
+            let corner = size * 0.92;
+            let hexagon = Path::new(|builder| {
+                for k in 0..6 {
+                    let angle = std::f32::consts::FRAC_PI_3 * k as f32;
+                    let point = Point::new(x + corner * angle.cos(), y + corner * angle.sin());
+                    if k == 0 {
+                        builder.move_to(point);
+                    } else {
+                        builder.line_to(point);
+                    }
+                }
+                builder.close();
+            });
+
+            let (background_color, text_color) = tile_colors(cell, self.rule, self.dark_mode);
+            frame.fill(&hexagon, background_color.scale_alpha(self.opacity));
+            frame.stroke(
+                &hexagon,
+                Stroke::default()
+                    .with_color(tile_border_color(cell, self.dark_mode).scale_alpha(self.opacity))
+                    .with_width(2.0),
+            );
+
+            if let Some(value) = cell.value() {
+                frame.fill_text(Text {
+                    content: value.to_string(),
+                    position: Point::new(x, y),
+                    color: text_color.scale_alpha(self.opacity),
+                    size: Pixels(size * 0.55),
+                    horizontal_alignment: alignment::Horizontal::Center,
+                    vertical_alignment: alignment::Vertical::Center,
+                    ..Text::default()
+                });
+            }
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
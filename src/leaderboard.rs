@@ -0,0 +1,173 @@
+use crate::Message;
+use crate::focus::focusable;
+use iced::widget::{button, column, container, row, text};
+use iced::{Element, Length};
+use iced_2048::date::Date;
+use iced_2048::game::{Mode, UndoPolicy, Variant};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+
+const MAX_ENTRIES: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScoreEntry {
+    pub score: u32,
+    pub max_tile: u32,
+    pub moves: u32,
+    pub date: Date,
+    #[serde(default)]
+    pub undo_policy: UndoPolicy,
+}
+
+/// High-score tables, one per ranked mode.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Leaderboard {
+    tables: BTreeMap<String, Vec<ScoreEntry>>,
+}
+
+impl Leaderboard {
+    pub fn entries(&self, mode: Mode, variant: Variant, undo_policy: UndoPolicy) -> &[ScoreEntry] {
+        self.tables
+            .get(&mode.leaderboard_key(variant, undo_policy))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Every table that has an entry, by its key from
+    /// [`Mode::leaderboard_key`].
+    pub fn tables(&self) -> impl Iterator<Item = (&str, &[ScoreEntry])> {
+        self.tables
+            .iter()
+            .map(|(key, entries)| (key.as_str(), entries.as_slice()))
+    }
+
+    pub fn best(
+        &self,
+        mode: Mode,
+        variant: Variant,
+        undo_policy: UndoPolicy,
+    ) -> Option<&ScoreEntry> {
+        self.entries(mode, variant, undo_policy).first()
+    }
+
+    /// Adds an entry to the table for its undo policy, returning its rank if
+    /// it made the table.
+    pub fn submit(&mut self, mode: Mode, variant: Variant, entry: ScoreEntry) -> Option<usize> {
+        let key = mode.leaderboard_key(variant, entry.undo_policy);
+        self.insert(key, entry)
+    }
+
+    fn insert(&mut self, key: String, entry: ScoreEntry) -> Option<usize> {
+        let table = self.tables.entry(key).or_default();
+        let rank = table
+            .iter()
+            .position(|existing| {
+                (entry.score, entry.max_tile) > (existing.score, existing.max_tile)
+            })
+            .unwrap_or(table.len());
+        if rank >= MAX_ENTRIES {
+            return None;
+        }
+        table.insert(rank, entry);
+        table.truncate(MAX_ENTRIES);
+        Some(rank)
+    }
+
+    pub fn save(&self) {
+        if let Some(path) = iced_2048::data_file_path("scores.json")
+            && let Ok(json) = serde_json::to_string_pretty(self)
+        {
+            let _ = fs::write(path, json);
+        }
+    }
+
+    pub fn load() -> Self {
+        let mut leaderboard: Self = iced_2048::data_file_path("scores.json")
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        leaderboard.split_classic_tables();
+        leaderboard
+    }
+
+    /// Moves classic games with limited or no undo out of the tables they
+    /// shared with unlimited undo, before the policy was part of the key.
+    fn split_classic_tables(&mut self) {
+        let mut moved = Vec::new();
+        for (key, table) in &mut self.tables {
+            if !key.starts_with("classic") {
+                continue;
+            }
+            table.retain(|entry| {
+                let suffix = entry.undo_policy.key_suffix();
+                if suffix.is_empty() || key.ends_with(&suffix) {
+                    return true;
+                }
+                moved.push((format!("{key}{suffix}"), entry.clone()));
+                false
+            });
+        }
+        self.tables.retain(|_, table| !table.is_empty());
+        for (key, entry) in moved {
+            self.insert(key, entry);
+        }
+    }
+}
+
+pub fn view_scores(
+    leaderboard: &Leaderboard,
+    mode: Mode,
+    variant: Variant,
+    undo_policy: UndoPolicy,
+) -> Element<'_, Message> {
+    let details = match mode {
+        Mode::Classic => format!("{}, {}", variant.label(), undo_policy.label()),
+        _ => variant.label(),
+    };
+    let title = text(format!("High Scores: {} ({details})", mode.label())).size(30);
+
+    let entries = leaderboard.entries(mode, variant, undo_policy);
+    let table: Element<Message> = if entries.is_empty() {
+        text("No scores yet.").size(16).into()
+    } else {
+        let header = row![
+            text("#").size(16).width(30),
+            text("Score").size(16).width(90),
+            text("Max tile").size(16).width(90),
+            text("Moves").size(16).width(70),
+            text("Date").size(16).width(110),
+            text("Undo").size(16).width(140),
+        ]
+        .spacing(12);
+        let rows = entries.iter().enumerate().map(|(rank, entry)| {
+            row![
+                text(rank + 1).size(16).width(30),
+                text(entry.score).size(16).width(90),
+                text(entry.max_tile).size(16).width(90),
+                text(entry.moves).size(16).width(70),
+                text(entry.date.to_string()).size(16).width(110),
+                text(entry.undo_policy.label()).size(16).width(140),
+            ]
+            .spacing(12)
+            .into()
+        });
+        column![header].extend(rows).spacing(8).into()
+    };
+
+    let buttons = row![
+        focusable(button("Play").padding(12), Message::StartMode(mode)),
+        focusable(button("Back").padding(12), Message::ShowModes),
+    ]
+    .spacing(12);
+
+    let content = column![title, table, buttons]
+        .spacing(20)
+        .align_x(iced::Alignment::Center);
+
+    container(content)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center(Length::Fill)
+        .padding(20)
+        .into()
+}
@@ -0,0 +1,28 @@
+//! The game engine and its agents, plus the networking used by the game and
+//! the relay server.
+
+pub mod analysis;
+pub mod api;
+pub mod bitboard;
+pub mod board;
+pub mod date;
+pub mod describe;
+pub mod env;
+pub mod export;
+pub mod game;
+mod http;
+pub mod montecarlo;
+pub mod notation;
+pub mod ntuple;
+pub mod palette;
+pub mod paths;
+pub mod protocol;
+pub mod puzzle;
+pub mod relay;
+pub mod rng;
+pub mod rules;
+pub mod simulate;
+pub mod spectate;
+pub mod strategy;
+
+pub use paths::{config_file_path, data_file_path};
@@ -1,18 +1,49 @@
+mod assist;
+mod cli;
+mod daily;
+mod editor;
+mod focus;
+mod hex;
+mod leaderboard;
+mod puzzles;
+mod race;
+mod review;
+mod settings;
+mod versus;
+
+use assist::{Assist, ENGINES, Engine};
+use cli::{Cli, Command};
+use daily::{DailyLog, DailyResult};
+use editor::Editor;
+use focus::{focusable, focusable_maybe};
+use iced::futures::SinkExt;
+use iced::futures::channel::oneshot;
 use iced::keyboard;
-use iced::widget::{button, column, container, row, text};
+use iced::widget::{button, column, container, row, stack, text};
 use iced::{Element, Subscription, Task, Theme};
-use rand::Rng;
-use serde::{Deserialize, Serialize};
+use iced_2048::api;
+use iced_2048::board::{Board, Shape};
+use iced_2048::date::Date;
+use iced_2048::describe;
+use iced_2048::export;
+use iced_2048::game::{
+    Cell, Direction, Game, MOVE_LIMITS, Mode, OBSTACLES, Obstacles, SHAPES, TIME_ATTACK_MINUTES,
+    UNDO_POLICIES, UndoPolicy, Variant,
+};
+use iced_2048::notation::{Position, Record};
+use iced_2048::palette::{self, Rgb};
+use iced_2048::paths;
+use iced_2048::rules::{MERGE_RULES, MergeRule};
+use iced_2048::spectate::Spectators;
+use leaderboard::{Leaderboard, ScoreEntry};
+use puzzles::PuzzleLibrary;
+use race::Race;
+use review::GameReview;
+use settings::{KeyAction, Settings, SettingsScreen, Theme as ThemeSetting, key_label};
 use std::fs;
 use std::path::PathBuf;
-
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
-}
+use std::time::{SystemTime, UNIX_EPOCH};
+use versus::Versus;
 
 #[derive(Debug, Clone)]
 pub enum Message {
@@ -22,404 +53,746 @@ pub enum Message {
     Quit,
     Undo,
     KeyPressed(keyboard::Key, keyboard::Modifiers),
+    EndPreview,
+    StartDaily,
+    ShowCalendar,
+    ShowGame,
+    CalendarMonth(i32),
+    SelectDay(Date),
+    CopyDailyShare(Date),
+    ShowModes,
+    StartMode(Mode),
+    ShowScores(Mode),
+    Tick,
+    SetUndoPolicy(UndoPolicy),
+    SetObstacles(Obstacles),
+    SetMergeRule(MergeRule),
+    SetShape(Shape),
+    StartVersus,
+    ShowRace,
+    RaceAddressChanged(String),
+    RaceNameChanged(String),
+    ConnectRace,
+    LeaveRace,
+    RaceEvent(race::Event),
+    Api(api::Request),
+    SetEngine(Engine),
+    Hint,
+    HintReady(Board, Result<Direction, String>),
+    ToggleAutoplay,
+    AutoplayTick,
+    Analyze,
+    ReviewEvent(review::Event),
+    SelectTurn(usize),
+    ShowPuzzles,
+    StartPuzzle(String),
+    ShowEditor,
+    Editor(editor::Action),
+    CopyPosition,
+    CopyRecord,
+    PastePosition,
+    PositionPasted(Option<String>),
+    ExportBoard,
+    Screenshot,
+    ScreenshotTaken(iced::window::Screenshot),
+    ExportReplay,
+    ReplayExported(Result<PathBuf, String>),
+    ShowSettings,
+    Settings(settings::Action),
+    CheckSystemTheme,
+    SystemTheme(Option<bool>),
+    ReadBoard,
+    FocusNext,
+    FocusPrevious,
+    PressFocused,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub struct Game {
-    board: [[u32; 4]; 4],
-    score: u32,
-    game_over: bool,
-    won: bool,
-    dark_mode: bool,
-    history: Vec<GameState>,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Screen {
+    Game,
+    Calendar,
+    Modes,
+    Scores(Mode),
+    Versus,
+    Race,
+    Review,
+    Puzzles,
+    Editor,
+    Settings,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-struct GameState {
-    board: [[u32; 4]; 4],
-    score: u32,
-    game_over: bool,
-    won: bool,
+struct App {
+    game: Game,
+    screen: Screen,
+    daily: DailyLog,
+    calendar_month: (i32, u32),
+    selected_day: Option<Date>,
+    leaderboard: Leaderboard,
+    /// Policy for the next classic game started from the modes screen.
+    undo_policy: UndoPolicy,
+    /// Variant for the next game started from the modes screen.
+    variant: Variant,
+    /// The current hot-seat match, if one has been started.
+    versus: Option<Versus>,
+    /// The networked race, kept so the lobby remembers its address and name.
+    race: Race,
+    /// Streams the game to browsers when enabled with `ICED_2048_SPECTATE`.
+    spectators: Option<Spectators>,
+    /// Where the control API listens when enabled with `ICED_2048_API`.
+    api_address: Option<String>,
+    /// Hints and autoplay for the main game.
+    assist: Assist,
+    /// Review of the last analyzed game.
+    review: Option<GameReview>,
+    /// Move shown over the board while Shift is held, with the board it
+    /// leaves before the spawn and the points it scores.
+    preview: Option<(Direction, Board, u32)>,
+    puzzles: PuzzleLibrary,
+    /// The board editor, kept so edits survive leaving the screen.
+    editor: Option<Editor>,
+    /// Result of the last copy or paste, until the next move.
+    notice: Option<String>,
+    settings: Settings,
+    /// The settings being edited, while the settings screen is open.
+    settings_screen: Option<SettingsScreen>,
+    /// Whether the desktop prefers dark colors, while the theme follows it.
+    system_dark_mode: Option<bool>,
+    /// The button with keyboard focus, by its place in the screen's chain.
+    focus: Option<(Screen, usize)>,
+    /// The last move or board read out, in words.
+    announcement: Option<String>,
+    /// Whether announcements also go to stdout.
+    announce: bool,
 }
 
-impl Game {
-    fn new() -> Self {
-        let mut game = Game {
-            board: [[0; 4]; 4],
-            score: 0,
-            game_over: false,
-            won: false,
-            dark_mode: false,
-            history: Vec::new(),
-        };
-        game.add_random_tile();
-        game.add_random_tile();
-        game
+impl App {
+    fn new(
+        game: Game,
+        settings: Settings,
+        spectators: Option<Spectators>,
+        api_address: Option<String>,
+    ) -> Self {
+        let today = Date::today();
+        if let Some(spectators) = &spectators {
+            spectators.publish(&game.player_state());
+        }
+        App {
+            screen: Screen::Game,
+            daily: DailyLog::load(),
+            calendar_month: (today.year, today.month),
+            selected_day: None,
+            leaderboard: Leaderboard::load(),
+            undo_policy: settings.undo_policy,
+            variant: Variant {
+                shape: settings.shape,
+                ..game.variant
+            },
+            versus: None,
+            race: Race::new(game.dark_mode),
+            spectators,
+            api_address,
+            assist: Assist::default(),
+            review: None,
+            preview: None,
+            puzzles: PuzzleLibrary::load(),
+            editor: None,
+            notice: None,
+            system_dark_mode: (settings.theme == ThemeSetting::System)
+                .then(settings::system_dark_mode)
+                .flatten(),
+            settings,
+            settings_screen: None,
+            focus: None,
+            announcement: None,
+            announce: false,
+            game,
+        }
     }
 
-    fn new_with_theme(dark_mode: bool) -> Self {
-        let mut game = Game {
-            board: [[0; 4]; 4],
-            score: 0,
-            game_over: false,
-            won: false,
-            dark_mode,
-            history: Vec::new(),
-        };
-        game.add_random_tile();
-        game.add_random_tile();
-        game
+    /// The focused button, if it's on the screen being shown.
+    fn focused(&self) -> Option<usize> {
+        self.focus
+            .filter(|&(screen, _)| screen == self.screen)
+            .map(|(_, index)| index)
     }
 
-    fn add_random_tile(&mut self) {
-        let mut empty_cells = Vec::new();
-        for i in 0..4 {
-            for j in 0..4 {
-                if self.board[i][j] == 0 {
-                    empty_cells.push((i, j));
-                }
-            }
-        }
-
-        if !empty_cells.is_empty() {
-            let mut rng = rand::rng();
-            let (i, j) = empty_cells[rng.random_range(0..empty_cells.len())];
-            self.board[i][j] = if rng.random_bool(0.9) { 2 } else { 4 };
+    /// Puts a sentence where screen readers can get it.
+    fn announce(&mut self, sentence: String) {
+        if self.announce {
+            println!("{sentence}");
         }
+        self.announcement = Some(sentence);
     }
 
-    fn move_tiles(&mut self, direction: Direction) -> bool {
-        // Save current state before making a move
-        self.save_state();
-
-        let old_board = self.board;
+    /// Puts new settings into effect and keeps them.
+    fn apply_settings(&mut self, settings: Settings) {
+        self.system_dark_mode = (settings.theme == ThemeSetting::System)
+            .then(settings::system_dark_mode)
+            .flatten();
+        self.set_dark_mode(
+            settings
+                .theme
+                .is_dark(self.system_dark_mode, settings.manual_theme),
+        );
+        self.undo_policy = settings.undo_policy;
+        self.variant.shape = settings.shape;
+        self.settings = settings;
+        self.settings.save();
+    }
 
-        match direction {
-            Direction::Left => self.move_left(),
-            Direction::Right => self.move_right(),
-            Direction::Up => self.move_up(),
-            Direction::Down => self.move_down(),
+    fn set_dark_mode(&mut self, dark_mode: bool) {
+        self.game.dark_mode = dark_mode;
+        self.game.save();
+        if let Some(versus) = &mut self.versus {
+            versus.set_dark_mode(dark_mode);
         }
+        self.race.set_dark_mode(dark_mode);
+    }
 
-        let moved = old_board != self.board;
-        if moved {
-            self.add_random_tile();
-            self.check_game_state();
-        } else {
-            // If no move was made, remove the saved state
-            self.history.pop();
+    /// Persists the game, the day's result when playing a daily challenge,
+    /// solved puzzles, and the final score of any other finished game.
+    fn save(&mut self) {
+        if let Mode::Daily(date) = self.game.mode {
+            self.daily.record(date, DailyResult::of(&self.game));
+            self.daily.save();
         }
+        if let (Mode::Puzzle(_), Some(key)) = (self.game.mode, &self.game.puzzle)
+            && self.game.won
+            && self.puzzles.mark_solved(key)
+        {
+            self.puzzles.save();
+        }
+        let unranked = matches!(self.game.mode, Mode::Daily(_) | Mode::Puzzle(_));
+        if !unranked && self.game.game_over && !self.game.recorded {
+            let entry = ScoreEntry {
+                score: self.game.score,
+                max_tile: self.game.max_tile(),
+                moves: self.game.moves,
+                date: Date::today(),
+                undo_policy: self.game.undo_policy,
+            };
+            self.leaderboard
+                .submit(self.game.mode, self.game.variant, entry);
+            self.leaderboard.save();
+            self.game.recorded = true;
+        }
+        self.game.save();
+    }
 
-        moved
+    fn show_calendar(&mut self, date: Date) {
+        self.screen = Screen::Calendar;
+        self.calendar_month = (date.year, date.month);
+        self.selected_day = self.daily.get(date).map(|_| date);
     }
+}
 
-    fn move_left(&mut self) {
-        for row in 0..4 {
-            let mut line = [0; 4];
-            let mut pos = 0;
+fn update(app: &mut App, message: Message) -> Task<Message> {
+    let screen = app.screen;
+    let task = handle_message(app, message);
+    // The Time Attack clock stops off the game screen, and only its last
+    // tick still needs saving
+    if screen == Screen::Game && app.screen != Screen::Game {
+        app.save();
+    }
+    if let Some(spectators) = &app.spectators {
+        spectators.publish(&app.game.player_state());
+    }
+    task
+}
 
-            // Move all tiles to the left
-            for col in 0..4 {
-                if self.board[row][col] != 0 {
-                    line[pos] = self.board[row][col];
-                    pos += 1;
-                }
+fn handle_message(app: &mut App, message: Message) -> Task<Message> {
+    let game = &mut app.game;
+    match message {
+        Message::Move(direction) => {
+            if !game.game_over && app.screen == Screen::Game {
+                let (_, merges) = game.slide(direction);
+                let sentence = if game.move_tiles(direction) {
+                    describe::move_result(game, direction, &merges)
+                } else {
+                    describe::blocked(direction)
+                };
+                app.assist.hint = None;
+                app.preview = None;
+                app.notice = None;
+                app.save();
+                app.announce(sentence);
             }
-
-            // Merge adjacent tiles
-            for i in 0..3 {
-                if line[i] != 0 && line[i] == line[i + 1] {
-                    line[i] *= 2;
-                    self.score += line[i];
-                    if line[i] == 2048 && !self.won {
-                        self.won = true;
+        }
+        Message::NewGame => {
+            let current_dark_mode = game.dark_mode;
+            *game = match game.mode {
+                // There's only one attempt at a daily challenge
+                Mode::Daily(_) => Game::new_with_theme(current_dark_mode),
+                // Puzzles start over from their position
+                Mode::Puzzle(_) => {
+                    match game.puzzle.as_deref().and_then(|key| app.puzzles.find(key)) {
+                        Some((pack, puzzle)) => puzzle.start(pack, current_dark_mode),
+                        None => Game::new_with_theme(current_dark_mode),
                     }
-                    line[i + 1] = 0;
                 }
-            }
-
-            // Move tiles left again after merging
-            let mut merged_line = [0; 4];
-            pos = 0;
-            for &item in &line {
-                if item != 0 {
-                    merged_line[pos] = item;
-                    pos += 1;
+                // Other modes restart with the same limit and undo policy
+                mode => {
+                    Game::new_with_mode(mode, game.undo_policy, game.variant, current_dark_mode)
                 }
+            };
+            app.screen = Screen::Game;
+            app.preview = None;
+            app.notice = None;
+            app.save();
+            let sentence = format!("New game. {}", describe::board(&app.game.board));
+            app.announce(sentence);
+        }
+        Message::ToggleDarkMode => {
+            let theme = ThemeSetting::of(!game.dark_mode);
+            app.set_dark_mode(theme == ThemeSetting::Dark);
+            // Picking by hand stops following the system
+            app.settings.set_theme(theme);
+            app.settings.save();
+            if let Some(screen) = &mut app.settings_screen {
+                screen.draft.set_theme(theme);
             }
-
-            self.board[row] = merged_line;
         }
-    }
-
-    fn move_right(&mut self) {
-        for row in 0..4 {
-            let mut line = [0; 4];
-            let mut pos = 3;
-
-            // Move all tiles to the right
-            for col in (0..4).rev() {
-                if self.board[row][col] != 0 {
-                    line[pos] = self.board[row][col];
-                    pos = pos.saturating_sub(1);
-                }
+        Message::Quit => {
+            app.save();
+            return iced::exit();
+        }
+        Message::Undo => {
+            if app.screen == Screen::Game && game.undo() {
+                app.preview = None;
+                game.save();
+                let sentence = format!("Undid a move, score {}", game.score);
+                app.announce(sentence);
             }
-
-            // Merge adjacent tiles
-            for i in (1..4).rev() {
-                if line[i] != 0 && line[i] == line[i - 1] {
-                    line[i] *= 2;
-                    self.score += line[i];
-                    if line[i] == 2048 && !self.won {
-                        self.won = true;
+        }
+        Message::KeyPressed(key, modifiers) => {
+            if app.screen == Screen::Settings
+                && let Some(screen) = &mut app.settings_screen
+                && screen.key_pressed(&key)
+            {
+                return Task::none();
+            }
+            if app.focus.is_some_and(|(screen, _)| screen == app.screen) {
+                // Space presses the focused button, as Enter does, and
+                // Escape leaves the buttons for the board
+                match key {
+                    keyboard::Key::Named(keyboard::key::Named::Space) => {
+                        return handle_message(app, Message::PressFocused);
                     }
-                    line[i - 1] = 0;
+                    keyboard::Key::Named(keyboard::key::Named::Escape) => {
+                        app.focus = None;
+                        return Task::none();
+                    }
+                    _ => {}
                 }
             }
-
-            // Move tiles right again after merging
-            let mut merged_line = [0; 4];
-            pos = 3;
-            for i in (0..4).rev() {
-                if line[i] != 0 {
-                    merged_line[pos] = line[i];
-                    pos = pos.saturating_sub(1);
+            let action = app.settings.keys.action(&key, game.board.shape());
+            // Arrow keys are for moving, whatever the bindings
+            let direction = match action {
+                Some(KeyAction::Move(direction)) => Some(direction),
+                _ if matches!(key, keyboard::Key::Named(_)) => {
+                    key_direction(game.board.shape(), &key)
+                }
+                _ => None,
+            };
+            if action == Some(KeyAction::ToggleTheme) {
+                return handle_message(app, Message::ToggleDarkMode);
+            } else if app.screen == Screen::Versus {
+                if let Some(versus) = &mut app.versus {
+                    versus.key_pressed(&key);
+                }
+            } else if app.screen == Screen::Race {
+                app.race.key_pressed(&key);
+            } else if app.screen == Screen::Review {
+                if let Some(review) = &mut app.review {
+                    review.key_pressed(&key);
+                }
+            } else if let Some(message) = match action {
+                Some(KeyAction::Undo) => Some(Message::Undo),
+                Some(KeyAction::Hint) => Some(Message::Hint),
+                Some(KeyAction::Autoplay) => Some(Message::ToggleAutoplay),
+                Some(KeyAction::ReadBoard) => Some(Message::ReadBoard),
+                _ => None,
+            } {
+                return handle_message(app, message);
+            } else if key == keyboard::Key::Named(keyboard::key::Named::Space)
+                && app.screen == Screen::Game
+            {
+                game.reset_tiles();
+                app.preview = None;
+                game.save();
+            } else if let Some(direction) = direction {
+                if modifiers.shift() {
+                    // Shift only previews the move, for as long as it's held
+                    app.preview = (!game.game_over).then(|| {
+                        let (after, merges) = game.slide(direction);
+                        (direction, after, merges.iter().sum())
+                    });
+                } else {
+                    return handle_message(app, Message::Move(direction));
                 }
             }
-
-            self.board[row] = merged_line;
         }
-    }
-
-    fn move_up(&mut self) {
-        for col in 0..4 {
-            let mut line = [0; 4];
-            let mut pos = 0;
-
-            // Move all tiles up
-            for row in 0..4 {
-                if self.board[row][col] != 0 {
-                    line[pos] = self.board[row][col];
-                    pos += 1;
-                }
+        Message::EndPreview => {
+            app.preview = None;
+        }
+        Message::StartDaily => {
+            let today = Date::today();
+            if game.mode == Mode::Daily(today) {
+                app.screen = Screen::Game;
+            } else if app.daily.get(today).is_some() {
+                // Today's attempt has already been used up
+                app.show_calendar(today);
+            } else {
+                *game = Game::new_with_mode(
+                    Mode::Daily(today),
+                    UndoPolicy::Disabled,
+                    Variant::default(),
+                    game.dark_mode,
+                );
+                app.screen = Screen::Game;
+                app.save();
             }
-
-            // Merge adjacent tiles
-            for i in 0..3 {
-                if line[i] != 0 && line[i] == line[i + 1] {
-                    line[i] *= 2;
-                    self.score += line[i];
-                    if line[i] == 2048 && !self.won {
-                        self.won = true;
+        }
+        Message::ShowCalendar => {
+            let date = match game.mode {
+                Mode::Daily(date) => date,
+                _ => Date::today(),
+            };
+            app.show_calendar(date);
+        }
+        Message::ShowGame => {
+            app.screen = Screen::Game;
+        }
+        Message::CalendarMonth(offset) => {
+            let (year, month) = app.calendar_month;
+            let index = year * 12 + month as i32 - 1 + offset;
+            app.calendar_month = (index.div_euclid(12), index.rem_euclid(12) as u32 + 1);
+        }
+        Message::SelectDay(date) => {
+            app.selected_day = Some(date);
+        }
+        Message::CopyDailyShare(date) => {
+            if let Some(result) = app.daily.get(date) {
+                return iced::clipboard::write(result.share_text(date));
+            }
+        }
+        Message::ShowModes => {
+            app.screen = Screen::Modes;
+            app.settings_screen = None;
+        }
+        Message::ShowSettings => {
+            app.settings_screen = Some(SettingsScreen::new(&app.settings));
+            app.screen = Screen::Settings;
+        }
+        Message::Settings(action) => {
+            let Some(screen) = &mut app.settings_screen else {
+                return Task::none();
+            };
+            let apply = matches!(action, settings::Action::Apply);
+            screen.update(action);
+            if apply {
+                let settings = screen.draft.clone();
+                app.apply_settings(settings);
+            }
+        }
+        Message::StartMode(mode) => {
+            *game = Game::new_with_mode(mode, app.undo_policy, app.variant, game.dark_mode);
+            app.screen = Screen::Game;
+            app.save();
+        }
+        Message::ShowScores(mode) => {
+            app.screen = Screen::Scores(mode);
+        }
+        Message::Tick => {
+            game.tick();
+            // Not every second: leaving the game screen saves the clock
+            if game.game_over {
+                app.save();
+            }
+        }
+        Message::SetUndoPolicy(policy) => {
+            app.undo_policy = policy;
+        }
+        Message::SetObstacles(obstacles) => {
+            app.variant.obstacles = obstacles;
+        }
+        Message::SetMergeRule(rule) => {
+            app.variant.rule = rule;
+        }
+        Message::SetShape(shape) => {
+            app.variant.shape = shape;
+        }
+        Message::StartVersus => {
+            app.versus = Some(Versus::new(app.variant, game.dark_mode));
+            app.screen = Screen::Versus;
+        }
+        Message::ShowRace => {
+            app.race.set_dark_mode(game.dark_mode);
+            app.screen = Screen::Race;
+        }
+        Message::RaceAddressChanged(address) => {
+            app.race.address = address;
+        }
+        Message::RaceNameChanged(name) => {
+            app.race.name = name;
+        }
+        Message::ConnectRace => {
+            app.race.connect();
+        }
+        Message::LeaveRace => {
+            app.race.leave();
+        }
+        Message::RaceEvent(event) => {
+            app.race.handle(event);
+        }
+        Message::Api(request) => {
+            let message = match &request.command {
+                api::Command::State => None,
+                api::Command::Move(name) => match Direction::from_name(name) {
+                    Some(direction) if game.board.shape().directions().contains(&direction) => {
+                        Some(Message::Move(direction))
                     }
-                    line[i + 1] = 0;
-                }
+                    _ => {
+                        request.respond(Err(format!("no such direction on this board: {name}")));
+                        return Task::none();
+                    }
+                },
+                api::Command::Undo => Some(Message::Undo),
+                api::Command::NewGame => Some(Message::NewGame),
+            };
+            // Bots go through the same messages as the keyboard and buttons
+            let task = message.map_or_else(Task::none, |message| handle_message(app, message));
+            request.respond(Ok(app.game.player_state()));
+            return task;
+        }
+        Message::SetEngine(engine) => {
+            app.assist.set_engine(engine);
+        }
+        Message::Hint => {
+            if app.screen == Screen::Game {
+                return app.assist.hint(game);
             }
-
-            // Move tiles up again after merging
-            let mut merged_line = [0; 4];
-            pos = 0;
-            for &item in &line {
-                if item != 0 {
-                    merged_line[pos] = item;
-                    pos += 1;
+        }
+        Message::HintReady(board, hint) => {
+            app.assist.hint_ready(game, &board, hint);
+        }
+        Message::ToggleAutoplay => {
+            app.assist.autoplay = !app.assist.autoplay;
+            app.assist.hint = None;
+        }
+        Message::Analyze => {
+            if game.game_over {
+                if !app.review.as_ref().is_some_and(|review| review.is_of(game)) {
+                    let (review, task) = GameReview::start(game);
+                    app.review = Some(review);
+                    app.screen = Screen::Review;
+                    return task.map(Message::ReviewEvent);
                 }
+                app.screen = Screen::Review;
             }
-
-            for (row, &value) in merged_line.iter().enumerate() {
-                self.board[row][col] = value;
+        }
+        Message::ReviewEvent(event) => {
+            if let Some(review) = &mut app.review {
+                review.handle(event);
             }
         }
-    }
-
-    fn move_down(&mut self) {
-        for col in 0..4 {
-            let mut line = [0; 4];
-            let mut pos = 3;
-
-            // Move all tiles down
-            for row in (0..4).rev() {
-                if self.board[row][col] != 0 {
-                    line[pos] = self.board[row][col];
-                    pos = pos.saturating_sub(1);
-                }
+        Message::ShowPuzzles => {
+            app.screen = Screen::Puzzles;
+        }
+        Message::StartPuzzle(key) => {
+            if let Some((pack, puzzle)) = app.puzzles.find(&key) {
+                *game = puzzle.start(pack, game.dark_mode);
+                app.screen = Screen::Game;
+                app.preview = None;
+                app.save();
             }
-
-            // Merge adjacent tiles
-            for i in (1..4).rev() {
-                if line[i] != 0 && line[i] == line[i - 1] {
-                    line[i] *= 2;
-                    self.score += line[i];
-                    if line[i] == 2048 && !self.won {
-                        self.won = true;
+        }
+        Message::ShowEditor => {
+            if app.editor.is_none() {
+                app.editor = Some(Editor::new(game));
+            }
+            app.screen = Screen::Editor;
+        }
+        Message::Editor(action) => {
+            let Some(editor) = &mut app.editor else {
+                return Task::none();
+            };
+            match action {
+                editor::Action::FromGame => *editor = Editor::new(game),
+                editor::Action::Play => {
+                    if let Some(position) = editor.play(app.undo_policy, game.dark_mode) {
+                        *game = position;
+                        app.screen = Screen::Game;
+                        app.preview = None;
+                        app.save();
                     }
-                    line[i - 1] = 0;
                 }
-            }
-
-            // Move tiles down again after merging
-            let mut merged_line = [0; 4];
-            pos = 3;
-            for i in (0..4).rev() {
-                if line[i] != 0 {
-                    merged_line[pos] = line[i];
-                    pos = pos.saturating_sub(1);
+                editor::Action::SavePuzzle => {
+                    if editor.save_puzzle(app.puzzles.folder.as_deref()) {
+                        app.puzzles = PuzzleLibrary::load();
+                    }
                 }
+                action => editor.update(action),
             }
-
-            for (row, &value) in merged_line.iter().enumerate() {
-                self.board[row][col] = value;
+        }
+        Message::CopyPosition => {
+            if app.screen == Screen::Game {
+                app.notice = Some("Position copied".to_string());
+                return iced::clipboard::write(Position::of(game).to_string());
             }
         }
-    }
-
-    fn check_game_state(&mut self) {
-        // Check if there are any empty cells
-        for row in 0..4 {
-            for col in 0..4 {
-                if self.board[row][col] == 0 {
-                    return; // Game continues
+        Message::CopyRecord => {
+            if app.screen == Screen::Game {
+                match Record::of(game) {
+                    Some(record) => {
+                        app.notice = Some("Moves copied".to_string());
+                        return iced::clipboard::write(record.to_string());
+                    }
+                    None => {
+                        app.notice =
+                            Some("This game's moves weren't kept from the start".to_string());
+                    }
                 }
             }
         }
-
-        // Check if any adjacent tiles can be merged
-        for row in 0..4 {
-            for col in 0..4 {
-                let current = self.board[row][col];
-                if (row < 3 && self.board[row + 1][col] == current)
-                    || (col < 3 && self.board[row][col + 1] == current)
-                {
-                    return; // Game continues
-                }
+        Message::PastePosition => {
+            if app.screen == Screen::Game {
+                return iced::clipboard::read().map(Message::PositionPasted);
             }
         }
-
-        self.game_over = true;
-    }
-
-    fn save_state(&mut self) {
-        let state = GameState {
-            board: self.board,
-            score: self.score,
-            game_over: self.game_over,
-            won: self.won,
-        };
-        self.history.push(state);
-    }
-
-    fn undo(&mut self) -> bool {
-        if let Some(previous_state) = self.history.pop() {
-            self.board = previous_state.board;
-            self.score = previous_state.score;
-            self.game_over = previous_state.game_over;
-            self.won = previous_state.won;
-            true
-        } else {
-            false
+        Message::PositionPasted(text) => {
+            let pasted = Record::parse(text.as_deref().unwrap_or_default())
+                .and_then(|record| record.replay(game.undo_policy, game.dark_mode));
+            match pasted {
+                Ok(pasted) => {
+                    *game = pasted;
+                    app.preview = None;
+                    app.notice = Some("Position pasted".to_string());
+                    app.save();
+                }
+                Err(error) => app.notice = Some(format!("Couldn't paste a position: {error}")),
+            }
         }
-    }
-
-    fn can_undo(&self) -> bool {
-        !self.history.is_empty()
-    }
-
-    fn reset_tiles(&mut self) {
-        // Reset board and game state but preserve history and settings
-        self.board = [[0; 4]; 4];
-        self.score = 0;
-        self.game_over = false;
-        self.won = false;
-        // Keep history and dark_mode unchanged
-        self.add_random_tile();
-        self.add_random_tile();
-    }
-
-    fn save_file_path() -> Option<PathBuf> {
-        dirs::data_dir().and_then(|mut path| {
-            path.push("iced-2048");
-            std::fs::create_dir_all(&path).ok()?;
-            path.push("save.json");
-            Some(path)
-        })
-    }
-
-    fn save(&self) {
-        if let Some(path) = Self::save_file_path() {
-            if let Ok(json) = serde_json::to_string_pretty(self) {
-                let _ = fs::write(path, json);
+        Message::ExportBoard => {
+            if app.screen == Screen::Game {
+                let result = export_path("board", "png").and_then(|path| {
+                    export::save_board(&game.board, game.variant.rule, game.dark_mode, &path)?;
+                    Ok(path)
+                });
+                app.notice = Some(match result {
+                    Ok(path) => format!("Board saved to {}", path.display()),
+                    Err(error) => format!("Couldn't export the board: {error}"),
+                });
             }
         }
-    }
-
-    fn load() -> Option<Self> {
-        if let Some(path) = Self::save_file_path() {
-            if let Ok(contents) = fs::read_to_string(path) {
-                if let Ok(game) = serde_json::from_str::<Game>(&contents) {
-                    return Some(game);
+        Message::ExportReplay => {
+            let Some(record) = Record::of(game) else {
+                app.notice = Some("This game's moves weren't kept from the start".to_string());
+                return Task::none();
+            };
+            let path = match export_path("replay", "gif") {
+                Ok(path) => path,
+                Err(error) => {
+                    app.notice = Some(format!("Couldn't export the replay: {error}"));
+                    return Task::none();
                 }
+            };
+            app.notice = Some("Exporting the replay…".to_string());
+            let options = export::ReplayOptions {
+                delay: app.settings.speed.replay_delay(),
+                dark_mode: game.dark_mode,
+                ..export::ReplayOptions::default()
+            };
+            // Encoding takes a while for long games, so it runs off the UI
+            let (sender, receiver) = oneshot::channel();
+            std::thread::spawn(move || {
+                let result = export::save_replay(&record, &options, &path).map(|()| path);
+                let _ = sender.send(result);
+            });
+            return Task::perform(receiver, |result| {
+                Message::ReplayExported(
+                    result.unwrap_or_else(|_| Err("the export stopped".to_string())),
+                )
+            });
+        }
+        Message::CheckSystemTheme => {
+            // Asking the desktop can mean a D-Bus round trip
+            let (sender, receiver) = oneshot::channel();
+            std::thread::spawn(move || {
+                let _ = sender.send(settings::system_dark_mode());
+            });
+            return Task::perform(receiver, |result| {
+                Message::SystemTheme(result.unwrap_or_default())
+            });
+        }
+        Message::SystemTheme(system_dark_mode) => {
+            if app.settings.theme == ThemeSetting::System
+                && system_dark_mode != app.system_dark_mode
+            {
+                app.system_dark_mode = system_dark_mode;
+                let dark_mode = app
+                    .settings
+                    .theme
+                    .is_dark(system_dark_mode, app.settings.manual_theme);
+                app.set_dark_mode(dark_mode);
             }
         }
-        None
-    }
-}
-
-fn update(game: &mut Game, message: Message) -> Task<Message> {
-    match message {
-        Message::Move(direction) => {
-            if !game.game_over {
-                game.move_tiles(direction);
-                game.save();
+        Message::ReadBoard => {
+            if app.screen == Screen::Game {
+                let sentence = describe::game(game);
+                app.announce(sentence);
             }
         }
-        Message::NewGame => {
-            let current_dark_mode = game.dark_mode;
-            *game = Game::new_with_theme(current_dark_mode);
-            game.save();
+        Message::FocusNext => {
+            app.focus = focus::step(app.focused(), true).map(|index| (app.screen, index));
         }
-        Message::ToggleDarkMode => {
-            game.dark_mode = !game.dark_mode;
-            game.save();
+        Message::FocusPrevious => {
+            app.focus = focus::step(app.focused(), false).map(|index| (app.screen, index));
         }
-        Message::Quit => {
-            return iced::exit();
+        Message::PressFocused => {
+            if let Some(message) = app.focused().and_then(focus::message) {
+                return handle_message(app, message);
+            }
         }
-        Message::Undo => {
-            game.undo();
-            game.save();
-        }
-        Message::KeyPressed(key, _modifiers) => {
-            if let keyboard::Key::Named(named_key) = key {
-                match named_key {
-                    keyboard::key::Named::ArrowUp => {
-                        if !game.game_over {
-                            game.move_tiles(Direction::Up);
-                            game.save();
-                        }
-                    }
-                    keyboard::key::Named::ArrowDown => {
-                        if !game.game_over {
-                            game.move_tiles(Direction::Down);
-                            game.save();
-                        }
-                    }
-                    keyboard::key::Named::ArrowLeft => {
-                        if !game.game_over {
-                            game.move_tiles(Direction::Left);
-                            game.save();
-                        }
-                    }
-                    keyboard::key::Named::ArrowRight => {
-                        if !game.game_over {
-                            game.move_tiles(Direction::Right);
-                            game.save();
-                        }
-                    }
-                    keyboard::key::Named::Space => {
-                        game.reset_tiles();
-                        game.save();
+        Message::ReplayExported(result) => {
+            app.notice = Some(match result {
+                Ok(path) => format!("Replay saved to {}", path.display()),
+                Err(error) => format!("Couldn't export the replay: {error}"),
+            });
+        }
+        Message::Screenshot => {
+            return iced::window::get_latest()
+                .and_then(iced::window::screenshot)
+                .map(Message::ScreenshotTaken);
+        }
+        Message::ScreenshotTaken(screenshot) => {
+            let result = export_path("screenshot", "png").and_then(|path| {
+                let png = export::encode_png(
+                    screenshot.size.width,
+                    screenshot.size.height,
+                    &screenshot.bytes,
+                )?;
+                fs::write(&path, png).map_err(|error| error.to_string())?;
+                Ok(path)
+            });
+            app.notice = Some(match result {
+                Ok(path) => format!("Screenshot saved to {}", path.display()),
+                Err(error) => format!("Couldn't save the screenshot: {error}"),
+            });
+        }
+        Message::SelectTurn(turn) => {
+            if let Some(review) = &mut app.review {
+                review.select(turn);
+            }
+        }
+        Message::AutoplayTick => {
+            if app.screen == Screen::Game {
+                match app.assist.choose(game) {
+                    Ok(direction) => return handle_message(app, Message::Move(direction)),
+                    Err(message) => {
+                        app.assist.autoplay = false;
+                        app.assist.hint = Some(message);
                     }
-                    _ => {}
                 }
             }
         }
@@ -427,91 +800,234 @@ fn update(game: &mut Game, message: Message) -> Task<Message> {
     Task::none()
 }
 
-fn view(game: &Game) -> Element<Message> {
-    let title = text("2048").size(50);
+fn view(app: &App) -> Element<'_, Message> {
+    focus::begin(app.focused());
+    match app.screen {
+        Screen::Game => view_game(app),
+        Screen::Calendar => daily::view_calendar(&app.daily, app.calendar_month, app.selected_day),
+        Screen::Modes => view_modes(app),
+        Screen::Scores(mode) => {
+            leaderboard::view_scores(&app.leaderboard, mode, app.variant, app.undo_policy)
+        }
+        Screen::Versus => match &app.versus {
+            Some(versus) => versus.view(),
+            None => view_game(app),
+        },
+        Screen::Race => app.race.view(),
+        Screen::Puzzles => puzzles::view_puzzles(&app.puzzles),
+        Screen::Review => match &app.review {
+            Some(review) => review.view(),
+            None => view_game(app),
+        },
+        Screen::Editor => match &app.editor {
+            Some(editor) => editor.view(app.game.dark_mode),
+            None => view_game(app),
+        },
+        Screen::Settings => match &app.settings_screen {
+            Some(screen) => screen.view(),
+            None => view_game(app),
+        },
+    }
+}
+
+fn view_modes(app: &App) -> Element<'_, Message> {
+    let title = text("Game Modes").size(40);
+
+    let mode_row = |label: String, play: Message, scores: Option<Mode>| {
+        let mut mode_row = row![
+            text(label).size(18).width(320),
+            focusable(button("Play").padding(8), play),
+        ]
+        .spacing(12)
+        .align_y(iced::Alignment::Center);
+        if let Some(mode) = scores {
+            mode_row = mode_row.push(focusable(
+                button("Scores").padding(8),
+                Message::ShowScores(mode),
+            ));
+        }
+        mode_row.into()
+    };
+
+    let ranked_modes = TIME_ATTACK_MINUTES
+        .iter()
+        .map(|&minutes| Mode::TimeAttack {
+            seconds: minutes * 60,
+        })
+        .chain(
+            MOVE_LIMITS
+                .iter()
+                .map(|&moves| Mode::LimitedMoves { moves }),
+        );
+
+    let undo_policies = options(
+        "Undo",
+        &UNDO_POLICIES,
+        app.undo_policy,
+        UndoPolicy::label,
+        Message::SetUndoPolicy,
+    );
+    let obstacles = options(
+        "Obstacles",
+        &OBSTACLES,
+        app.variant.obstacles,
+        Obstacles::label,
+        Message::SetObstacles,
+    );
+
+    let merge_rules = options(
+        "Rule",
+        &MERGE_RULES,
+        app.variant.rule,
+        MergeRule::label,
+        Message::SetMergeRule,
+    );
+
+    let shapes = options(
+        "Board",
+        &SHAPES,
+        app.variant.shape,
+        Shape::label,
+        Message::SetShape,
+    );
+
+    let engines = options(
+        "Engine",
+        &ENGINES,
+        app.assist.engine,
+        Engine::label,
+        Message::SetEngine,
+    );
+
+    let mut rows: Vec<Element<Message>> = vec![
+        undo_policies,
+        shapes,
+        merge_rules,
+        obstacles,
+        mode_row(
+            format!("{} ({})", Mode::Classic.label(), app.undo_policy.label()),
+            Message::StartMode(Mode::Classic),
+            Some(Mode::Classic),
+        ),
+        mode_row("Daily Challenge".to_string(), Message::StartDaily, None),
+        mode_row(
+            "Versus (hot-seat, WASD vs arrows)".to_string(),
+            Message::StartVersus,
+            None,
+        ),
+        mode_row("Race over the network".to_string(), Message::ShowRace, None),
+        mode_row("Puzzles".to_string(), Message::ShowPuzzles, None),
+        mode_row("Board editor".to_string(), Message::ShowEditor, None),
+        mode_row("Settings".to_string(), Message::ShowSettings, None),
+    ];
+    rows.extend(ranked_modes.map(|mode| {
+        let label = match app.leaderboard.best(mode, app.variant, app.undo_policy) {
+            Some(best) => format!("{} (best {})", mode.label(), best.score),
+            None => mode.label(),
+        };
+        mode_row(label, Message::StartMode(mode), Some(mode))
+    }));
+    rows.push(engines);
+
+    let back_button = focusable(button("Back").padding(12), Message::ShowGame);
+
+    let content = column![title, column(rows).spacing(10), back_button]
+        .spacing(20)
+        .align_x(iced::Alignment::Center);
+
+    container(content)
+        .width(iced::Length::Fill)
+        .height(iced::Length::Fill)
+        .center(iced::Length::Fill)
+        .padding(20)
+        .into()
+}
+
+/// A row of preset buttons with the current choice highlighted.
+fn options<'a, T: Copy + PartialEq>(
+    label: &'a str,
+    presets: &[T],
+    current: T,
+    name: impl Fn(T) -> String,
+    on_press: impl Fn(T) -> Message,
+) -> Element<'a, Message> {
+    let buttons = presets.iter().map(|&preset| {
+        let style = if preset == current {
+            button::primary
+        } else {
+            button::secondary
+        };
+        focusable(
+            button(text(name(preset)).size(14)).style(style).padding(8),
+            on_press(preset),
+        )
+    });
+    row![text(label).size(16).width(90)]
+        .extend(buttons)
+        .spacing(8)
+        .align_y(iced::Alignment::Center)
+        .into()
+}
 
-    let score_text = text(format!("Score: {}", game.score)).size(20);
+/// Background and text colors of a cell for the active theme.
+fn tile_colors(cell: Cell, rule: MergeRule, dark_mode: bool) -> (iced::Color, iced::Color) {
+    let (background, text) = palette::tile_colors(cell, rule, dark_mode);
+    (color(background), color(text))
+}
+
+/// Outline color of a cell for the active theme.
+fn tile_border_color(cell: Cell, dark_mode: bool) -> iced::Color {
+    color(palette::border_color(cell, dark_mode))
+}
+
+fn color(rgb: Rgb) -> iced::Color {
+    iced::Color::from_rgb(rgb.r, rgb.g, rgb.b)
+}
+
+/// Opacity of the board previewing a move over the real one.
+const GHOST_OPACITY: f32 = 0.8;
+
+/// Draws a board of any shape.
+fn view_board(board: &Board, rule: MergeRule, dark_mode: bool) -> Element<'_, Message> {
+    view_board_with_opacity(board, rule, dark_mode, 1.0)
+}
+
+fn view_board_with_opacity(
+    board: &Board,
+    rule: MergeRule,
+    dark_mode: bool,
+    opacity: f32,
+) -> Element<'_, Message> {
+    match board.shape() {
+        Shape::Square(_) => view_grid(board, rule, dark_mode, opacity),
+        Shape::Hex(_) => hex::view_board(board, rule, dark_mode, opacity),
+    }
+}
 
+/// Square boards are a grid of containers.
+fn view_grid(
+    board: &Board,
+    rule: MergeRule,
+    dark_mode: bool,
+    opacity: f32,
+) -> Element<'_, Message> {
     let mut board_rows = Vec::new();
-    for board_row in 0..4 {
+    for board_row in board.rows() {
         let mut board_cols = Vec::new();
-        for board_col in 0..4 {
-            let tile_value = game.board[board_row][board_col];
+        for cell in board_row {
+            let tile_value = cell.value().unwrap_or(0);
             let tile_text = if tile_value == 0 {
                 String::new()
             } else {
                 tile_value.to_string()
             };
-
-            let (background_color, text_color) = if game.dark_mode {
-                // Dark mode colors
-                let bg = match tile_value {
-                    0 => iced::Color::from_rgb(0.2, 0.2, 0.2),
-                    2 => iced::Color::from_rgb(0.3, 0.3, 0.3),
-                    4 => iced::Color::from_rgb(0.4, 0.4, 0.35),
-                    8 => iced::Color::from_rgb(0.5, 0.4, 0.2),
-                    16 => iced::Color::from_rgb(0.6, 0.3, 0.2),
-                    32 => iced::Color::from_rgb(0.7, 0.2, 0.2),
-                    64 => iced::Color::from_rgb(0.8, 0.1, 0.1),
-                    128 => iced::Color::from_rgb(0.7, 0.6, 0.1),
-                    256 => iced::Color::from_rgb(0.8, 0.7, 0.1),
-                    512 => iced::Color::from_rgb(0.9, 0.8, 0.1),
-                    1024 => iced::Color::from_rgb(1.0, 0.9, 0.1),
-                    2048 => iced::Color::from_rgb(1.0, 0.8, 0.0),
-                    4096 => iced::Color::from_rgb(0.9, 0.1, 0.9),
-                    8192 => iced::Color::from_rgb(0.8, 0.2, 0.9),
-                    16384 => iced::Color::from_rgb(0.7, 0.3, 0.9),
-                    32768 => iced::Color::from_rgb(0.6, 0.4, 0.9),
-                    65536 => iced::Color::from_rgb(0.5, 0.5, 0.9),
-                    131072 => iced::Color::from_rgb(0.4, 0.6, 0.9),
-                    262144 => iced::Color::from_rgb(0.3, 0.7, 0.9),
-                    524288 => iced::Color::from_rgb(0.2, 0.8, 0.9),
-                    1048576 => iced::Color::from_rgb(0.1, 0.9, 0.9),
-                    2097152 => iced::Color::from_rgb(0.0, 1.0, 0.9),
-                    4194304 => iced::Color::from_rgb(0.0, 0.9, 1.0),
-                    _ => iced::Color::from_rgb(0.3, 0.6, 0.9),
-                };
-                let text_color = if tile_value <= 4 && tile_value > 0 {
-                    iced::Color::from_rgb(0.8, 0.8, 0.8)
-                } else {
-                    iced::Color::WHITE
-                };
-                (bg, text_color)
-            } else {
-                // Light mode colors (original)
-                let bg = match tile_value {
-                    0 => iced::Color::from_rgb(0.8, 0.8, 0.8),
-                    2 => iced::Color::from_rgb(0.9, 0.9, 0.85),
-                    4 => iced::Color::from_rgb(0.9, 0.85, 0.8),
-                    8 => iced::Color::from_rgb(0.9, 0.7, 0.5),
-                    16 => iced::Color::from_rgb(0.9, 0.6, 0.4),
-                    32 => iced::Color::from_rgb(0.9, 0.5, 0.3),
-                    64 => iced::Color::from_rgb(0.9, 0.4, 0.2),
-                    128 => iced::Color::from_rgb(0.9, 0.8, 0.4),
-                    256 => iced::Color::from_rgb(0.9, 0.8, 0.3),
-                    512 => iced::Color::from_rgb(0.9, 0.8, 0.2),
-                    1024 => iced::Color::from_rgb(0.9, 0.8, 0.1),
-                    2048 => iced::Color::from_rgb(1.0, 0.8, 0.0),
-                    4096 => iced::Color::from_rgb(0.8, 0.1, 0.8),
-                    8192 => iced::Color::from_rgb(0.7, 0.2, 0.8),
-                    16384 => iced::Color::from_rgb(0.6, 0.3, 0.8),
-                    32768 => iced::Color::from_rgb(0.5, 0.4, 0.8),
-                    65536 => iced::Color::from_rgb(0.4, 0.5, 0.8),
-                    131072 => iced::Color::from_rgb(0.3, 0.6, 0.8),
-                    262144 => iced::Color::from_rgb(0.2, 0.7, 0.8),
-                    524288 => iced::Color::from_rgb(0.1, 0.8, 0.8),
-                    1048576 => iced::Color::from_rgb(0.0, 0.9, 0.8),
-                    2097152 => iced::Color::from_rgb(0.0, 0.8, 0.9),
-                    4194304 => iced::Color::from_rgb(0.0, 0.7, 0.9),
-                    _ => iced::Color::from_rgb(0.2, 0.2, 0.2),
-                };
-                let text_color = if tile_value <= 4 && tile_value > 0 {
-                    iced::Color::from_rgb(0.4, 0.4, 0.4)
-                } else {
-                    iced::Color::WHITE
-                };
-                (bg, text_color)
-            };
+            let (background_color, text_color) = tile_colors(cell, rule, dark_mode);
+            let border_color = tile_border_color(cell, dark_mode);
+            let (background_color, text_color, border_color) = (
+                background_color.scale_alpha(opacity),
+                text_color.scale_alpha(opacity),
+                border_color.scale_alpha(opacity),
+            );
 
             let tile = container(text(tile_text).size(28).color(text_color))
                 .width(90)
@@ -521,13 +1037,9 @@ fn view(game: &Game) -> Element<Message> {
                 .style(move |_theme: &Theme| iced::widget::container::Style {
                     background: Some(iced::Background::Color(background_color)),
                     border: iced::Border {
-                        color: if game.dark_mode {
-                            iced::Color::from_rgb(0.4, 0.4, 0.4)
-                        } else {
-                            iced::Color::from_rgb(0.7, 0.7, 0.7)
-                        },
-                        width: 2.0,
-                        radius: 8.0.into(),
+                        color: border_color,
+                        width: if cell == Cell::Block { 4.0 } else { 2.0 },
+                        radius: if cell == Cell::Block { 2.0 } else { 8.0 }.into(),
                     },
                     ..Default::default()
                 });
@@ -537,36 +1049,174 @@ fn view(game: &Game) -> Element<Message> {
         board_rows.push(row(board_cols).spacing(8).into());
     }
 
-    let board = column(board_rows).spacing(8);
+    column(board_rows).spacing(8).into()
+}
 
-    let new_game_button = button("New Game").on_press(Message::NewGame).padding(12);
+fn view_game(app: &App) -> Element<'_, Message> {
+    let game = &app.game;
+    let title = text("2048").size(50);
 
-    let toggle_theme_button = button(if game.dark_mode {
-        "Light Mode"
-    } else {
-        "Dark Mode"
-    })
-    .on_press(Message::ToggleDarkMode)
-    .padding(12);
+    let score_text = match (game.mode, game.remaining()) {
+        (Mode::Classic, _) => text(format!("Score: {}", game.score)),
+        (mode, Some(remaining)) => text(format!(
+            "{} • {remaining} • Score: {}",
+            mode.label(),
+            game.score
+        )),
+        (mode, None) => text(format!("{} • Score: {}", mode.label(), game.score)),
+    }
+    .size(20);
+
+    let board = view_board(&game.board, game.variant.rule, game.dark_mode);
+    let board: Element<Message> = match &app.preview {
+        Some((_, after, _)) if *after != game.board => stack![
+            board,
+            view_board_with_opacity(after, game.variant.rule, game.dark_mode, GHOST_OPACITY)
+        ]
+        .into(),
+        _ => board,
+    };
+
+    let new_game_label = match game.mode {
+        Mode::Puzzle(_) => "Retry",
+        _ => "New Game",
+    };
+    let new_game_button = focusable(button(new_game_label).padding(12), Message::NewGame);
+
+    let toggle_theme_button = focusable(
+        button(if game.dark_mode {
+            "Light Mode"
+        } else {
+            "Dark Mode"
+        })
+        .padding(12),
+        Message::ToggleDarkMode,
+    );
 
     let undo_button = if game.can_undo() {
-        button("Undo").on_press(Message::Undo).padding(12)
+        focusable(button(text(game.undo_label())).padding(12), Message::Undo)
     } else {
-        button("Undo").padding(12) // Disabled button
+        focusable_maybe(button(text(game.undo_label())).padding(12), None) // Disabled button
     };
 
-    let status_text = if game.won && !game.game_over {
+    let status_text = if let Some((direction, after, points)) = &app.preview {
+        if *after == game.board {
+            text(format!("Moving {} changes nothing", direction.label())).size(18)
+        } else {
+            text(format!(
+                "Moving {} scores {points} • release Shift to cancel",
+                direction.label()
+            ))
+            .size(18)
+        }
+    } else if let Some(notice) = &app.notice {
+        text(notice).size(18)
+    } else if let Some(hint) = &app.assist.hint {
+        text(hint).size(18)
+    } else if app.assist.autoplay && !game.game_over {
+        text(format!(
+            "{} is playing • {} to stop",
+            app.assist.engine.label(),
+            key_label(app.settings.keys.autoplay)
+        ))
+        .size(18)
+    } else if let Mode::Puzzle(_) = game.mode
+        && game.game_over
+    {
+        if game.won {
+            text("Solved! Pick another puzzle from Modes → Puzzles.").size(18)
+        } else {
+            text("Not quite. Press Retry to try again.").size(18)
+        }
+    } else if game.won && !game.game_over {
         text("You Win! Keep playing or start a new game.").size(18)
+    } else if game.game_over && game.mode.is_ranked() {
+        text(format!(
+            "Finished! Final score {}. Press New Game to try again.",
+            game.score
+        ))
+        .size(18)
     } else if game.game_over {
-        text("Game Over! Try again.").size(18)
+        text("Game Over! Try again, or review your moves.").size(18)
+    } else {
+        let keys = &app.settings.keys;
+        let undo = key_label(keys.undo);
+        let undo_hint = match game.undos_left() {
+            _ if game.mode != Mode::Classic => String::new(),
+            Some(0) => " • No undos left".to_string(),
+            Some(left) => format!(" • {undo} to undo ({left} left)"),
+            None => format!(" • {undo} to undo"),
+        };
+        let move_hint = match game.board.shape() {
+            Shape::Square(_) => format!("{}/arrows", keys.move_keys(game.board.shape())),
+            Shape::Hex(_) => keys.move_keys(game.board.shape()),
+        };
+        text(format!(
+            "Use {move_hint} to move (hold Shift to preview) • Space to reset{undo_hint} • {} for a hint • {} to autoplay • {} for dark mode • {} to read the board • Tab for the buttons • Ctrl+, for settings • Ctrl+Q to quit",
+            key_label(keys.hint),
+            key_label(keys.autoplay),
+            key_label(keys.toggle_theme),
+            key_label(keys.read_board),
+        ))
+        .size(16)
+    };
+
+    let daily_button = match game.mode {
+        Mode::Daily(date) if game.game_over => focusable(
+            button("Copy Result").padding(12),
+            Message::CopyDailyShare(date),
+        ),
+        _ => focusable(button("Daily").padding(12), Message::StartDaily),
+    };
+
+    let calendar_button = focusable(button("Calendar").padding(12), Message::ShowCalendar);
+
+    let modes_button = focusable(button("Modes").padding(12), Message::ShowModes);
+
+    // Finished games can be reviewed instead of asking for a hint
+    let hint_button = if game.game_over {
+        focusable(button("Review").padding(12), Message::Analyze)
     } else {
-        text("Use WASD/arrows to move • Space to reset • Z to undo • P for dark mode • Ctrl+Q to quit")
-            .size(16)
+        focusable(button("Hint").padding(12), Message::Hint)
     };
 
-    let button_row = row![new_game_button, toggle_theme_button, undo_button].spacing(12);
+    // Finished games can be saved as an animation
+    let replay_button = game
+        .game_over
+        .then(|| focusable(button("Export GIF").padding(12), Message::ExportReplay));
+
+    let autoplay_button = focusable(
+        button(if app.assist.autoplay {
+            "Stop"
+        } else {
+            "Autoplay"
+        })
+        .padding(12),
+        Message::ToggleAutoplay,
+    );
+
+    let button_row = row![
+        new_game_button,
+        toggle_theme_button,
+        undo_button,
+        daily_button,
+        calendar_button,
+        modes_button,
+        hint_button,
+        autoplay_button
+    ]
+    .push_maybe(replay_button)
+    .spacing(12);
+
+    // The last move in words, for anyone who can't make out the tiles
+    let announcement = app
+        .announcement
+        .as_ref()
+        .map(|sentence| text(sentence).size(14));
 
-    let content = column![title, score_text, board, status_text, button_row]
+    let content = column![title, score_text, board, status_text]
+        .push_maybe(announcement)
+        .push(button_row)
         .spacing(20)
         .align_x(iced::Alignment::Center);
 
@@ -578,63 +1228,245 @@ fn view(game: &Game) -> Element<Message> {
         .into()
 }
 
-fn subscription(_game: &Game) -> Subscription<Message> {
-    iced::keyboard::on_key_press(|key, modifiers| match key {
+/// Maps a movement key to a direction on the given board shape. Square
+/// boards use WASD and the arrows; hex boards use Q/W/E/A/S/D.
+fn key_direction(shape: Shape, key: &keyboard::Key) -> Option<Direction> {
+    use keyboard::key::Named;
+
+    let hex = matches!(shape, Shape::Hex(_));
+    match key.as_ref() {
+        keyboard::Key::Named(Named::ArrowUp) => Some(Direction::Up),
+        keyboard::Key::Named(Named::ArrowDown) => Some(Direction::Down),
+        keyboard::Key::Named(Named::ArrowLeft) if !hex => Some(Direction::Left),
+        keyboard::Key::Named(Named::ArrowRight) if !hex => Some(Direction::Right),
+        keyboard::Key::Character(char_str) => match char_str.to_lowercase().as_str() {
+            "w" => Some(Direction::Up),
+            "s" => Some(Direction::Down),
+            "a" if hex => Some(Direction::DownLeft),
+            "d" if hex => Some(Direction::DownRight),
+            "q" if hex => Some(Direction::UpLeft),
+            "e" if hex => Some(Direction::UpRight),
+            "a" => Some(Direction::Left),
+            "d" => Some(Direction::Right),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// How often a theme that follows the system checks for a change.
+const SYSTEM_THEME_POLL: std::time::Duration = std::time::Duration::from_secs(2);
+
+fn subscription(app: &App) -> Subscription<Message> {
+    // The clock only runs while the game is on screen
+    let clock = match app.game.mode {
+        Mode::TimeAttack { .. } if !app.game.game_over && app.screen == Screen::Game => {
+            iced::time::every(std::time::Duration::from_secs(1)).map(|_| Message::Tick)
+        }
+        _ => Subscription::none(),
+    };
+
+    let keys = iced::keyboard::on_key_press(|key, modifiers| match key {
         keyboard::Key::Named(
             keyboard::key::Named::ArrowUp
             | keyboard::key::Named::ArrowDown
             | keyboard::key::Named::ArrowLeft
             | keyboard::key::Named::ArrowRight
-            | keyboard::key::Named::Space,
+            | keyboard::key::Named::Home
+            | keyboard::key::Named::End
+            | keyboard::key::Named::Space
+            | keyboard::key::Named::Escape,
         ) => Some(Message::KeyPressed(key, modifiers)),
+        keyboard::Key::Named(keyboard::key::Named::Tab) if modifiers.shift() => {
+            Some(Message::FocusPrevious)
+        }
+        keyboard::Key::Named(keyboard::key::Named::Tab) => Some(Message::FocusNext),
+        keyboard::Key::Named(keyboard::key::Named::Enter) => Some(Message::PressFocused),
         keyboard::Key::Character(ref char_str) => {
             if modifiers.control() {
                 match char_str.as_str() {
                     "q" | "Q" => Some(Message::Quit),
+                    "c" | "C" if modifiers.shift() => Some(Message::CopyRecord),
+                    "c" | "C" => Some(Message::CopyPosition),
+                    "v" | "V" => Some(Message::PastePosition),
+                    "e" | "E" if modifiers.shift() => Some(Message::Screenshot),
+                    "e" | "E" => Some(Message::ExportBoard),
+                    "," => Some(Message::ShowSettings),
                     _ => None,
                 }
             } else {
-                match char_str.as_str() {
-                    "w" | "W" => Some(Message::KeyPressed(
-                        keyboard::Key::Named(keyboard::key::Named::ArrowUp),
-                        modifiers,
-                    )),
-                    "a" | "A" => Some(Message::KeyPressed(
-                        keyboard::Key::Named(keyboard::key::Named::ArrowLeft),
-                        modifiers,
-                    )),
-                    "s" | "S" => Some(Message::KeyPressed(
-                        keyboard::Key::Named(keyboard::key::Named::ArrowDown),
-                        modifiers,
-                    )),
-                    "d" | "D" => Some(Message::KeyPressed(
-                        keyboard::Key::Named(keyboard::key::Named::ArrowRight),
-                        modifiers,
-                    )),
-                    "p" | "P" => Some(Message::ToggleDarkMode),
-                    "z" | "Z" => Some(Message::Undo),
-                    _ => None,
-                }
+                // Letters are looked up in the key bindings in `update`
+                Some(Message::KeyPressed(key.clone(), modifiers))
             }
         }
         _ => None,
-    })
+    });
+
+    // Letting go of Shift or the direction ends a move preview. Any letter
+    // counts, since moves can be bound to any of them
+    let releases = iced::keyboard::on_key_release(|key, _modifiers| match key.as_ref() {
+        keyboard::Key::Named(
+            keyboard::key::Named::Shift
+            | keyboard::key::Named::ArrowUp
+            | keyboard::key::Named::ArrowDown
+            | keyboard::key::Named::ArrowLeft
+            | keyboard::key::Named::ArrowRight,
+        )
+        | keyboard::Key::Character(_) => Some(Message::EndPreview),
+        _ => None,
+    });
+
+    let race = app.race.subscription().map(Message::RaceEvent);
+
+    let autoplay = if app.assist.autoplay && app.screen == Screen::Game && !app.game.game_over {
+        iced::time::every(app.settings.speed.autoplay_interval()).map(|_| Message::AutoplayTick)
+    } else {
+        Subscription::none()
+    };
+
+    let api = match &app.api_address {
+        Some(address) => api_requests(address.clone()),
+        None => Subscription::none(),
+    };
+
+    // The preference can only be read, not watched, so it's read again
+    let system_theme = if app.settings.theme == ThemeSetting::System {
+        iced::time::every(SYSTEM_THEME_POLL).map(|_| Message::CheckSystemTheme)
+    } else {
+        Subscription::none()
+    };
+
+    Subscription::batch([keys, releases, clock, race, api, autoplay, system_theme])
 }
 
-fn theme(game: &Game) -> Theme {
-    if game.dark_mode {
+fn theme(app: &App) -> Theme {
+    if app.game.dark_mode {
         Theme::Dark
     } else {
         Theme::Light
     }
 }
 
+/// Address from the environment variable `name`: `None` when unset, and
+/// `default` when set but empty.
+fn env_address(name: &str, default: &str) -> Option<String> {
+    let address = std::env::var(name).ok()?;
+    Some(match address.trim() {
+        "" => default.to_string(),
+        address => address.to_string(),
+    })
+}
+
+/// Starts the spectator endpoint if `ICED_2048_SPECTATE` is set.
+fn start_spectators() -> Option<Spectators> {
+    let address = env_address("ICED_2048_SPECTATE", iced_2048::spectate::DEFAULT_ADDRESS)?;
+    match Spectators::start(&address) {
+        Ok(spectators) => {
+            println!("Spectators can watch at http://{}/", spectators.address());
+            Some(spectators)
+        }
+        Err(error) => {
+            eprintln!("Couldn't start the spectator endpoint on {address}: {error}");
+            None
+        }
+    }
+}
+
+/// Starts the control API on `address`, returning the requests it receives.
+fn start_api(address: &str) -> Option<tokio::sync::mpsc::UnboundedReceiver<api::Request>> {
+    let listener = match std::net::TcpListener::bind(address) {
+        Ok(listener) => listener,
+        Err(error) => {
+            eprintln!("Couldn't start the control API on {address}: {error}");
+            return None;
+        }
+    };
+    if let Ok(address) = listener.local_addr() {
+        println!("Control API listening on http://{address}/");
+    }
+    let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+    std::thread::spawn(move || api::serve(listener, sender));
+    Some(receiver)
+}
+
+fn api_requests(address: String) -> Subscription<Message> {
+    Subscription::run_with_id(
+        address.clone(),
+        iced::stream::channel(100, move |mut output| async move {
+            let Some(mut requests) = start_api(&address) else {
+                return;
+            };
+            while let Some(request) = requests.recv().await {
+                let _ = output.send(Message::Api(request)).await;
+            }
+        }),
+    )
+}
+
+/// Runs the game without a window, driven only by the control API.
+fn run_headless(game: Game, settings: Settings, announce: bool, address: &str) {
+    let Some(mut requests) = start_api(address) else {
+        return;
+    };
+    let mut app = App::new(game, settings, start_spectators(), None);
+    app.announce = announce;
+    while let Some(request) = requests.blocking_recv() {
+        let _ = update(&mut app, Message::Api(request));
+    }
+}
+
+/// Where images exported from the window go: the pictures folder, or the
+/// data directory without one. Names carry the time, so that exports don't
+/// overwrite each other.
+fn export_path(kind: &str, extension: &str) -> Result<PathBuf, String> {
+    let folder = dirs::picture_dir()
+        .or_else(|| iced_2048::data_file_path("exports"))
+        .ok_or("there's no folder to save it in")?;
+    fs::create_dir_all(&folder).map_err(|error| format!("{}: {error}", folder.display()))?;
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    Ok(folder.join(format!("iced-2048-{kind}-{seconds}.{extension}")))
+}
+
+/// Reports a bad command line or failed command, and exits.
+fn fail(error: &str) -> ! {
+    eprintln!("{error}");
+    std::process::exit(2);
+}
+
 fn main() -> iced::Result {
+    let cli = Cli::parse_args();
+    // Before anything reads a file
+    paths::init(cli.dirs.overrides());
+    let play = match cli.command {
+        None => cli.play,
+        Some(Command::Play(play)) => play,
+        Some(command) => {
+            cli::run(command).unwrap_or_else(|error| fail(&error));
+            return Ok(());
+        }
+    };
+    play.use_save_file();
+    let settings = Settings::load();
+    let game = play.start(&settings).unwrap_or_else(|error| fail(&error));
+    let api_address = env_address("ICED_2048_API", api::DEFAULT_ADDRESS);
+    if std::env::var_os("ICED_2048_HEADLESS").is_some() {
+        run_headless(
+            game,
+            settings,
+            play.announce,
+            api_address.as_deref().unwrap_or(api::DEFAULT_ADDRESS),
+        );
+        return Ok(());
+    }
+
     iced::application("2048", update, view)
         .subscription(subscription)
         .theme(theme)
-        .run_with(|| {
-            let game = Game::load().unwrap_or_else(Game::new);
-            (game, Task::none())
+        .run_with(move || {
+            let mut app = App::new(game, settings, start_spectators(), api_address);
+            app.announce = play.announce;
+            (app, Task::none())
         })
 }
@@ -0,0 +1,125 @@
+//! A Monte Carlo agent: it plays each legal move followed by random games
+//! to the end, and picks the move whose games score best on average.
+//!
+//! Rollouts are spread over threads, but rollout `i` always uses the same
+//! seeds, so without a time budget the choice doesn't depend on the number
+//! of threads. Unlike the n-tuple network it plays every variant.
+
+use crate::game::{Direction, Game};
+use crate::rng::SpawnRng;
+use crate::strategy::Strategy;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone)]
+pub struct MonteCarlo {
+    /// Random games played after each legal move.
+    pub rollouts: u32,
+    /// Stop early once a choice has taken this long. Every move still gets
+    /// at least one rollout.
+    pub time_budget: Option<Duration>,
+    pub threads: usize,
+    rng: SpawnRng,
+}
+
+impl MonteCarlo {
+    /// An agent using every CPU core, whose rollouts are seeded from `seed`.
+    pub fn new(rollouts: u32, time_budget: Option<Duration>, seed: u64) -> Self {
+        MonteCarlo {
+            rollouts,
+            time_budget,
+            threads: thread::available_parallelism().map_or(1, |threads| threads.get()),
+            rng: SpawnRng::new(seed),
+        }
+    }
+
+    /// Average final score of the rollouts after each of `directions`.
+    pub fn evaluate(&mut self, game: &Game, directions: &[Direction]) -> Vec<f64> {
+        let deadline = self.time_budget.map(|budget| Instant::now() + budget);
+        let seed = self.rng.next_u64();
+        let rollouts = self.rollouts as usize;
+        let threads = self.threads.clamp(1, rollouts.max(1));
+
+        let results: Vec<(Vec<u64>, Vec<u32>)> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads)
+                .map(|worker| {
+                    scope.spawn(move || {
+                        let mut totals = vec![0; directions.len()];
+                        let mut counts = vec![0; directions.len()];
+                        for i in (worker..rollouts).step_by(threads) {
+                            if i >= threads && deadline.is_some_and(|end| Instant::now() >= end) {
+                                break;
+                            }
+                            for (d, &direction) in directions.iter().enumerate() {
+                                totals[d] += u64::from(rollout(game, direction, seed, i));
+                                counts[d] += 1;
+                            }
+                        }
+                        (totals, counts)
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap())
+                .collect()
+        });
+
+        (0..directions.len())
+            .map(|d| {
+                let total: u64 = results.iter().map(|(totals, _)| totals[d]).sum();
+                let count: u32 = results.iter().map(|(_, counts)| counts[d]).sum();
+                total as f64 / f64::from(count.max(1))
+            })
+            .collect()
+    }
+}
+
+impl Strategy for MonteCarlo {
+    fn name(&self) -> String {
+        format!("Monte Carlo ({} rollouts)", self.rollouts)
+    }
+
+    fn choose(&mut self, game: &Game) -> Option<Direction> {
+        if game.game_over {
+            return None;
+        }
+        let legal: Vec<Direction> = game
+            .board
+            .shape()
+            .directions()
+            .iter()
+            .copied()
+            .filter(|&direction| game.slide(direction).0 != game.board)
+            .collect();
+        let averages = self.evaluate(game, &legal);
+        legal
+            .into_iter()
+            .zip(averages)
+            // `max_by` keeps the last maximum; reversing keeps the first
+            .rev()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(direction, _)| direction)
+    }
+}
+
+/// Final score of rollout `index` after playing `direction`, moving at
+/// random until the game ends.
+fn rollout(game: &Game, direction: Direction, seed: u64, index: usize) -> u32 {
+    let mut rng = SpawnRng::new(seed ^ (index as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    let mut game = game.playout(rng.next_u64());
+    let directions = game.board.shape().directions();
+    if !game.move_tiles(direction) {
+        return game.score;
+    }
+    while !game.game_over {
+        // Try directions in order from a random one until something moves
+        let start = rng.below(directions.len());
+        let moved = (0..directions.len())
+            .any(|k| game.move_tiles(directions[(start + k) % directions.len()]));
+        if !moved {
+            break;
+        }
+    }
+    game.score
+}
@@ -0,0 +1,521 @@
+//! A compact text notation for positions and games, in the spirit of FEN
+//! and PGN in chess, for bug reports and sharing puzzles.
+//!
+//! A position is a single line of up to five fields:
+//!
+//! ```text
+//! 4x4 1..2/.b../..#./3... 1234 56 std
+//! ```
+//!
+//! 1. The shape: `4x4` for a square board, `hex2` for a hex board of radius 2.
+//! 2. The cells, row by row with `/` between rows, as drawn on screen; hex
+//!    rows are the cells of equal `r`. `.` is an empty cell and `#` a block.
+//!    A tile is its rank under the merge rule in base 36: `1` is the smallest
+//!    tile (2 in the standard game, so ranks are exponents there), `9` the
+//!    ninth, `a` the tenth, up to `z`. Larger tiles are written out in
+//!    brackets, as `(24157817)`.
+//! 3. The score, 0 if left out.
+//! 4. The number of moves played, 0 if left out.
+//! 5. The merge rule: `std`, `fib`, `threes` or `base3`; `std` if left out.
+//!
+//! A game record is the starting position on its first line and the moves
+//! on the following lines:
+//!
+//! ```text
+//! 4x4 ..../..../1.../...1 0 0 std
+//! R2@0 D4@4 L
+//! ```
+//!
+//! Each move is a direction letter from [`Direction::letter`], followed by
+//! what appeared after it: `value@cell` for a tile and `#@cell` for a
+//! block, with cells numbered from 0 in the order of save files. A move
+//! that left more than one, such as a tile and a growing obstacle's block,
+//! separates them with commas, as in `L2@10,#@6`. Spaces between moves are
+//! optional, so a plain `ULDR` is a valid move list.
+
+use crate::board::{Board, Shape};
+use crate::game::{Cell, Direction, Game, UndoPolicy};
+use crate::rules::MergeRule;
+use std::fmt;
+use std::path::Path;
+
+const RULE_CODES: [(MergeRule, &str); 4] = [
+    (MergeRule::Standard, "std"),
+    (MergeRule::Fibonacci, "fib"),
+    (MergeRule::Threes, "threes"),
+    (MergeRule::Base3, "base3"),
+];
+
+/// The highest rank written as a single base-36 digit.
+const MAX_DIGIT_RANK: usize = 35;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Position {
+    pub board: Board,
+    pub rule: MergeRule,
+    pub score: u32,
+    pub moves: u32,
+}
+
+impl Position {
+    pub fn of(game: &Game) -> Self {
+        Position {
+            board: game.board.clone(),
+            rule: game.variant.rule,
+            score: game.score,
+            moves: game.moves,
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = text.split_whitespace().collect();
+        let [shape, cells, rest @ ..] = fields.as_slice() else {
+            return Err("a position needs a shape and cells".to_string());
+        };
+        if rest.len() > 3 {
+            return Err(format!("unexpected field: {}", rest[3]));
+        }
+        let shape = parse_shape(shape)?;
+        let rule = match rest.get(2) {
+            Some(code) => parse_rule(code)?,
+            None => MergeRule::Standard,
+        };
+        let number = |index: usize, name: &str| match rest.get(index) {
+            Some(field) => field
+                .parse::<u32>()
+                .map_err(|_| format!("invalid {name}: {field}")),
+            None => Ok(0),
+        };
+        Ok(Position {
+            board: parse_cells(shape, rule, cells)?,
+            rule,
+            score: number(0, "score")?,
+            moves: number(1, "move count")?,
+        })
+    }
+
+    /// A game from this position, failing as [`Game::from_position`] does.
+    pub fn game(&self, undo_policy: UndoPolicy, dark_mode: bool) -> Result<Game, String> {
+        let mut game = Game::from_position(self.board.clone(), self.rule, undo_policy, dark_mode)?;
+        game.score = self.score;
+        game.moves = self.moves;
+        Ok(game)
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let shape = match self.board.shape() {
+            Shape::Square(size) => format!("{size}x{size}"),
+            Shape::Hex(radius) => format!("hex{radius}"),
+        };
+        let rows: Vec<String> = self
+            .board
+            .rows()
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .map(|cell| write_cell(cell, self.rule))
+                    .collect()
+            })
+            .collect();
+        let rule = RULE_CODES
+            .iter()
+            .find(|(rule, _)| *rule == self.rule)
+            .map_or("std", |(_, code)| code);
+        write!(
+            f,
+            "{shape} {} {} {} {rule}",
+            rows.join("/"),
+            self.score,
+            self.moves
+        )
+    }
+}
+
+/// A move and the cells filled after it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Move {
+    pub direction: Direction,
+    pub placed: Vec<(usize, Cell)>,
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let placed: Vec<String> = self
+            .placed
+            .iter()
+            .filter_map(|(i, cell)| match cell {
+                Cell::Tile(value) => Some(format!("{value}@{i}")),
+                Cell::Block => Some(format!("#@{i}")),
+                Cell::Empty => None,
+            })
+            .collect();
+        write!(f, "{}{}", self.direction.letter(), placed.join(","))
+    }
+}
+
+pub fn parse_moves(text: &str) -> Result<Vec<Move>, String> {
+    let mut moves: Vec<Move> = Vec::new();
+    let mut chars = text.chars().filter(|c| !c.is_whitespace()).peekable();
+    while let Some(c) = chars.next() {
+        if let Some(direction) = Direction::from_letter(c) {
+            moves.push(Move {
+                direction,
+                placed: Vec::new(),
+            });
+            continue;
+        }
+        let number = moves.len();
+        if number == 0 {
+            return Err(format!("expected a direction letter, found {c}"));
+        }
+        let cell = if c == '#' {
+            Cell::Block
+        } else if c.is_ascii_digit() {
+            let mut value = c.to_string();
+            while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                value.push(digit);
+            }
+            let value = value
+                .parse()
+                .map_err(|_| format!("move {number}: invalid tile {value}"))?;
+            Cell::Tile(value)
+        } else {
+            return Err(format!("move {number}: unexpected {c}"));
+        };
+        if chars.next() != Some('@') {
+            return Err(format!("move {number}: expected @ and a cell"));
+        }
+        let mut index = String::new();
+        while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+            index.push(digit);
+        }
+        let index = index
+            .parse()
+            .map_err(|_| format!("move {number}: expected a cell after @"))?;
+        moves[number - 1].placed.push((index, cell));
+        chars.next_if_eq(&',');
+    }
+    Ok(moves)
+}
+
+pub fn write_moves(moves: &[Move]) -> String {
+    moves
+        .iter()
+        .map(Move::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A game as a starting position and the moves played from it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub start: Position,
+    pub moves: Vec<Move>,
+}
+
+impl Record {
+    /// The record of `game` from its first move, or `None` if its history
+    /// doesn't reach back that far.
+    pub fn of(game: &Game) -> Option<Self> {
+        if game.moves == 0 {
+            return Some(Record {
+                start: Position::of(game),
+                moves: Vec::new(),
+            });
+        }
+        let turns = game.turns();
+        let first = turns.first()?;
+        let moves = turns
+            .iter()
+            .enumerate()
+            .map(|(k, turn)| {
+                let mut position = game.playout(0);
+                position.board = turn.board.clone();
+                let (slid, _) = position.slide(turn.direction);
+                let next = turns.get(k + 1).map_or(&game.board, |next| &next.board);
+                let placed = (0..slid.cells().len())
+                    .filter(|&i| slid[i] == Cell::Empty && next[i] != Cell::Empty)
+                    .map(|i| (i, next[i]))
+                    .collect();
+                Move {
+                    direction: turn.direction,
+                    placed,
+                }
+            })
+            .collect();
+        Some(Record {
+            start: Position {
+                board: first.board.clone(),
+                rule: game.variant.rule,
+                score: first.score,
+                moves: first.moves,
+            },
+            moves,
+        })
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+        let start = Position::parse(lines.next().unwrap_or_default())?;
+        let moves = parse_moves(&lines.collect::<Vec<_>>().join(" "))?;
+        Ok(Record { start, moves })
+    }
+
+    /// Reads a record from a file in text notation, or from a save file
+    /// whose history reaches back to the first move.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|error| error.to_string())?;
+        match serde_json::from_str::<Game>(&text) {
+            Ok(game) => Self::of(&game)
+                .ok_or_else(|| "the save doesn't have the game's moves from the start".to_string()),
+            Err(_) => Self::parse(&text),
+        }
+    }
+
+    /// Plays the moves from the starting position, placing the recorded
+    /// tiles after each one.
+    pub fn replay(&self, undo_policy: UndoPolicy, dark_mode: bool) -> Result<Game, String> {
+        self.replay_with(undo_policy, dark_mode, |_| Ok(()))
+    }
+
+    /// Replays the game like [`Record::replay`], showing `each` the
+    /// starting position and the game after every move.
+    pub fn replay_with(
+        &self,
+        undo_policy: UndoPolicy,
+        dark_mode: bool,
+        mut each: impl FnMut(&Game) -> Result<(), String>,
+    ) -> Result<Game, String> {
+        let mut game = self.start.game(undo_policy, dark_mode)?;
+        each(&game)?;
+        let cells = game.board.cells().len();
+        for (k, step) in self.moves.iter().enumerate() {
+            let number = k + 1;
+            if !game.board.shape().directions().contains(&step.direction) {
+                return Err(format!(
+                    "move {number}: can't move {} on this board",
+                    step.direction.label()
+                ));
+            }
+            for &(i, cell) in &step.placed {
+                if i >= cells {
+                    return Err(format!("move {number}: no cell {i} on this board"));
+                }
+                if let Cell::Tile(value) = cell
+                    && !self.start.rule.is_tile(value)
+                {
+                    return Err(format!(
+                        "move {number}: {value} isn't a tile in {}",
+                        self.start.rule.label()
+                    ));
+                }
+            }
+            if game.game_over {
+                return Err(format!("move {number}: the game is already over"));
+            }
+            if !game.move_placing(step.direction, &step.placed) {
+                return Err(format!(
+                    "move {number}: moving {} changes nothing",
+                    step.direction.label()
+                ));
+            }
+            each(&game)?;
+        }
+        Ok(game)
+    }
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.start)?;
+        write!(f, "{}", write_moves(&self.moves))
+    }
+}
+
+/// A board shape as written in positions: `4x4` or `hex2`.
+pub fn parse_shape(text: &str) -> Result<Shape, String> {
+    let invalid = || format!("invalid shape: {text}");
+    if let Some(radius) = text.strip_prefix("hex") {
+        let radius = radius.parse().map_err(|_| invalid())?;
+        return playable(Shape::Hex(radius));
+    }
+    match text.split_once('x') {
+        Some((width, height)) if width == height => {
+            let size = width.parse().map_err(|_| invalid())?;
+            playable(Shape::Square(size))
+        }
+        _ => Err(invalid()),
+    }
+}
+
+/// The shape if the game can be played on it, checked before anything
+/// allocates its cells.
+pub fn playable(shape: Shape) -> Result<Shape, String> {
+    match shape {
+        Shape::Square(3..=8) | Shape::Hex(1..=4) => Ok(shape),
+        _ => Err("boards go from 3x3 to 8x8, and from hex1 to hex4".to_string()),
+    }
+}
+
+/// A merge rule from its code in positions: `std`, `fib`, `threes` or
+/// `base3`.
+pub fn parse_rule(code: &str) -> Result<MergeRule, String> {
+    RULE_CODES
+        .iter()
+        .find(|&&(_, rule_code)| rule_code == code)
+        .map(|&(rule, _)| rule)
+        .ok_or_else(|| format!("unknown rule: {code}"))
+}
+
+fn parse_cells(shape: Shape, rule: MergeRule, text: &str) -> Result<Board, String> {
+    let rows = shape.rows();
+    let texts: Vec<&str> = text.split('/').collect();
+    if texts.len() != rows.len() {
+        return Err(format!(
+            "a {} board has {} rows, not {}",
+            shape.label(),
+            rows.len(),
+            texts.len()
+        ));
+    }
+
+    let mut board = Board::new(shape);
+    for (number, (row, text)) in rows.iter().zip(texts).enumerate() {
+        let cells = parse_row(rule, text)?;
+        if cells.len() != row.len() {
+            return Err(format!(
+                "row {} needs {} cells, not {}",
+                number + 1,
+                row.len(),
+                cells.len()
+            ));
+        }
+        for (&i, cell) in row.iter().zip(cells) {
+            board[i] = cell;
+        }
+    }
+    Ok(board)
+}
+
+fn parse_row(rule: MergeRule, text: &str) -> Result<Vec<Cell>, String> {
+    let mut cells = Vec::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        let cell = match c {
+            '.' => Cell::Empty,
+            '#' => Cell::Block,
+            '(' => {
+                let digits: String = chars.by_ref().take_while(|&c| c != ')').collect();
+                let value = digits
+                    .parse()
+                    .map_err(|_| format!("invalid tile: ({digits})"))?;
+                if !rule.is_tile(value) {
+                    return Err(format!("{value} isn't a tile in {}", rule.label()));
+                }
+                Cell::Tile(value)
+            }
+            _ => {
+                let rank = c
+                    .to_digit(36)
+                    .filter(|&rank| rank > 0)
+                    .ok_or_else(|| format!("invalid cell: {c}"))?;
+                let value = rule
+                    .tiles()
+                    .nth(rank as usize - 1)
+                    .ok_or_else(|| format!("no tile of rank {c} in {}", rule.label()))?;
+                Cell::Tile(value)
+            }
+        };
+        cells.push(cell);
+    }
+    Ok(cells)
+}
+
+fn write_cell(cell: Cell, rule: MergeRule) -> String {
+    match cell {
+        Cell::Empty => ".".to_string(),
+        Cell::Block => "#".to_string(),
+        Cell::Tile(value) => match rule.tiles().position(|tile| tile == value) {
+            Some(index) if index < MAX_DIGIT_RANK => char::from_digit(index as u32 + 1, 36)
+                .map_or_else(|| format!("({value})"), String::from),
+            _ => format!("({value})"),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(text: &str) {
+        let position = Position::parse(text).unwrap();
+        assert_eq!(position.to_string(), text);
+        assert_eq!(Position::parse(&position.to_string()), Ok(position));
+    }
+
+    #[test]
+    fn square_position_round_trips() {
+        round_trip("4x4 1..2/.b#./..3./...1 1234 56 std");
+        round_trip("3x3 .../.1./... 0 0 threes");
+    }
+
+    #[test]
+    fn hex_position_round_trips() {
+        round_trip("hex2 1../..#./...2./..../3.1 40 7 fib");
+    }
+
+    #[test]
+    fn record_round_trips_with_spawns() {
+        let text = "4x4 ..../..../1.../...1 0 0 std\nR2@0 D4@4,#@6 L";
+        let record = Record::parse(text).unwrap();
+        assert_eq!(
+            record.moves[1].placed,
+            vec![(4, Cell::Tile(4)), (6, Cell::Block)]
+        );
+        assert_eq!(record.to_string(), text);
+        assert_eq!(Record::parse(&record.to_string()), Ok(record));
+    }
+
+    #[test]
+    fn tiles_past_rank_z_are_written_out() {
+        let rule = MergeRule::Fibonacci;
+        let last_digit = rule.tiles().nth(MAX_DIGIT_RANK - 1).unwrap();
+        let first_long = rule.tiles().nth(MAX_DIGIT_RANK).unwrap();
+        assert_eq!(write_cell(Cell::Tile(last_digit), rule), "z");
+        assert_eq!(
+            write_cell(Cell::Tile(first_long), rule),
+            format!("({first_long})")
+        );
+        assert_eq!(
+            parse_row(rule, &format!("z({first_long})")),
+            Ok(vec![Cell::Tile(last_digit), Cell::Tile(first_long)])
+        );
+    }
+
+    #[test]
+    fn malformed_positions_are_rejected() {
+        assert_eq!(
+            Position::parse("4x4 ..../..../...."),
+            Err("a 4×4 board has 4 rows, not 3".to_string())
+        );
+        assert_eq!(
+            Position::parse("4x4 ..../.../..../...."),
+            Err("row 2 needs 4 cells, not 3".to_string())
+        );
+        assert_eq!(
+            Position::parse("4x4 ..../..../..../.... 0 0 hex"),
+            Err("unknown rule: hex".to_string())
+        );
+        assert!(Position::parse("hex9999999999 .").is_err());
+    }
+
+    #[test]
+    fn spawns_need_a_cell() {
+        assert_eq!(
+            parse_moves("L2@"),
+            Err("move 1: expected a cell after @".to_string())
+        );
+    }
+}
@@ -0,0 +1,399 @@
+//! An n-tuple network agent, trained by TD(λ) afterstate learning.
+//!
+//! The network values a board as the sum of weights looked up by a few
+//! "tuples" of cells, each under all eight rotations and reflections of the
+//! board. The agent plays the move whose points plus afterstate value is
+//! highest, and learns from whole games played against seeded spawns: after
+//! each game, every afterstate is moved towards its λ-return, last move
+//! first. With λ = 0 this is plain TD(0).
+//!
+//! It plays the standard 4×4 game only; see [`Bitboard`].
+//!
+//! # Weight files
+//!
+//! All numbers are little-endian.
+//!
+//! | Size          | Contents                                              |
+//! |---------------|-------------------------------------------------------|
+//! | 8 bytes       | Magic `N2048TUP`                                      |
+//! | u32           | Format version, currently 1                           |
+//! | f32           | Learning rate α                                       |
+//! | f32           | λ                                                     |
+//! | u64           | Games trained so far                                  |
+//! | u64           | State of the training RNG, to resume the same games   |
+//! | u32           | Number of tuples `n`                                  |
+//! | `n` times     | u32 length `k`, then `k` bytes of cell indices 0–15   |
+//! | `n` times     | `16^k` f32 weights for that tuple                     |
+//!
+//! Cells are numbered row by row from the top left. A tuple's weight index is
+//! the sum of `rank(cell_j) * 16^j` over its cells, where rank 0 is empty
+//! and rank `r` is the tile `2^r`.
+
+use crate::bitboard::{Bitboard, DIRECTIONS};
+use crate::game::{Direction, Game};
+use crate::rng::SpawnRng;
+use crate::strategy::Strategy;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+const MAGIC: &[u8; 8] = b"N2048TUP";
+const FORMAT_VERSION: u32 = 1;
+
+/// Rows, columns and 2×2 squares of four cells: about 1 MB of weights.
+pub const SMALL_TUPLES: &[&[u8]] = &[
+    &[0, 1, 2, 3],
+    &[4, 5, 6, 7],
+    &[0, 1, 4, 5],
+    &[1, 2, 5, 6],
+    &[5, 6, 9, 10],
+];
+
+/// The four 6-tuples of Yeh et al., which play far better but take about
+/// 270 MB of weights.
+pub const LARGE_TUPLES: &[&[u8]] = &[
+    &[0, 1, 2, 3, 4, 5],
+    &[4, 5, 6, 7, 8, 9],
+    &[0, 1, 2, 4, 5, 6],
+    &[4, 5, 6, 8, 9, 10],
+];
+
+/// Final score and largest tile of a training game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameStats {
+    pub score: u32,
+    pub max_tile: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NTupleNetwork {
+    tuples: Vec<Vec<u8>>,
+    weights: Vec<Vec<f32>>,
+    /// Every symmetric copy of every tuple, as (tuple index, cells).
+    features: Vec<(usize, Vec<u8>)>,
+    pub alpha: f32,
+    pub lambda: f32,
+    games_trained: u64,
+    rng: SpawnRng,
+}
+
+impl NTupleNetwork {
+    /// An untrained network whose training games are seeded from `seed`.
+    pub fn new(tuples: &[&[u8]], alpha: f32, lambda: f32, seed: u64) -> Self {
+        let tuples: Vec<Vec<u8>> = tuples.iter().map(|tuple| tuple.to_vec()).collect();
+        let weights = tuples
+            .iter()
+            .map(|tuple| vec![0.0; 1 << (4 * tuple.len())])
+            .collect();
+        Self::with_weights(tuples, weights, alpha, lambda, 0, SpawnRng::new(seed))
+    }
+
+    fn with_weights(
+        tuples: Vec<Vec<u8>>,
+        weights: Vec<Vec<f32>>,
+        alpha: f32,
+        lambda: f32,
+        games_trained: u64,
+        rng: SpawnRng,
+    ) -> Self {
+        let features = tuples
+            .iter()
+            .enumerate()
+            .flat_map(|(index, tuple)| {
+                symmetries(tuple)
+                    .into_iter()
+                    .map(move |cells| (index, cells))
+            })
+            .collect();
+        NTupleNetwork {
+            tuples,
+            weights,
+            features,
+            alpha,
+            lambda,
+            games_trained,
+            rng,
+        }
+    }
+
+    pub fn games_trained(&self) -> u64 {
+        self.games_trained
+    }
+
+    /// Estimated points still to come from an afterstate.
+    pub fn evaluate(&self, board: Bitboard) -> f32 {
+        self.features
+            .iter()
+            .map(|(tuple, cells)| self.weights[*tuple][index(board, cells)])
+            .sum()
+    }
+
+    fn adjust(&mut self, board: Bitboard, delta: f32) {
+        for (tuple, cells) in &self.features {
+            self.weights[*tuple][index(board, cells)] += delta;
+        }
+    }
+
+    /// The best move with its afterstate and the points it scores, or `None`
+    /// if no move changes the board.
+    pub fn best_move(&self, board: Bitboard) -> Option<(Direction, Bitboard, u32)> {
+        DIRECTIONS
+            .iter()
+            .filter_map(|&direction| {
+                let (after, score) = board.slide(direction);
+                (after != board).then_some((direction, after, score))
+            })
+            .map(|(direction, after, score)| {
+                (
+                    score as f32 + self.evaluate(after),
+                    (direction, after, score),
+                )
+            })
+            .fold(None, |best: Option<(f32, _)>, (value, play)| match best {
+                Some((best_value, _)) if best_value >= value => best,
+                _ => Some((value, play)),
+            })
+            .map(|(_, play)| play)
+    }
+
+    /// Plays one game with the current weights, then learns from it.
+    pub fn train_game(&mut self) -> GameStats {
+        let mut board = Bitboard(0);
+        board.spawn(&mut self.rng);
+        board.spawn(&mut self.rng);
+
+        let mut path = Vec::new();
+        let mut score = 0;
+        while let Some((_, after, reward)) = self.best_move(board) {
+            path.push((after, reward));
+            score += reward;
+            board = after;
+            board.spawn(&mut self.rng);
+        }
+
+        // λ-returns, from the final afterstate (worth nothing) backwards
+        let step = self.alpha / self.features.len() as f32;
+        let (mut next_reward, mut next_value, mut next_return) = (0.0, 0.0, 0.0);
+        for (t, &(after, reward)) in path.iter().enumerate().rev() {
+            let target = if t + 1 == path.len() {
+                0.0
+            } else {
+                next_reward + (1.0 - self.lambda) * next_value + self.lambda * next_return
+            };
+            let error = target - self.evaluate(after);
+            self.adjust(after, step * error);
+            next_reward = reward as f32;
+            next_value = self.evaluate(after);
+            next_return = target;
+        }
+
+        self.games_trained += 1;
+        GameStats {
+            score,
+            max_tile: board.max_tile(),
+        }
+    }
+
+    pub fn write_to(&self, writer: impl Write) -> io::Result<()> {
+        let mut writer = BufWriter::new(writer);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        writer.write_all(&self.alpha.to_le_bytes())?;
+        writer.write_all(&self.lambda.to_le_bytes())?;
+        writer.write_all(&self.games_trained.to_le_bytes())?;
+        writer.write_all(&self.rng.state().to_le_bytes())?;
+        writer.write_all(&(self.tuples.len() as u32).to_le_bytes())?;
+        for tuple in &self.tuples {
+            writer.write_all(&(tuple.len() as u32).to_le_bytes())?;
+            writer.write_all(tuple)?;
+        }
+        for table in &self.weights {
+            for weight in table {
+                writer.write_all(&weight.to_le_bytes())?;
+            }
+        }
+        writer.flush()
+    }
+
+    pub fn read_from(reader: impl Read) -> io::Result<Self> {
+        let mut reader = BufReader::new(reader);
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not an n-tuple weight file"));
+        }
+        let version = u32::from_le_bytes(read_array(&mut reader)?);
+        if version != FORMAT_VERSION {
+            return Err(invalid(&format!(
+                "unsupported weight file version {version}"
+            )));
+        }
+        let alpha = f32::from_le_bytes(read_array(&mut reader)?);
+        let lambda = f32::from_le_bytes(read_array(&mut reader)?);
+        let games_trained = u64::from_le_bytes(read_array(&mut reader)?);
+        let rng = SpawnRng::new(u64::from_le_bytes(read_array(&mut reader)?));
+
+        let count = u32::from_le_bytes(read_array(&mut reader)?);
+        let mut tuples = Vec::new();
+        for _ in 0..count {
+            let length = u32::from_le_bytes(read_array(&mut reader)?) as usize;
+            if !(1..=7).contains(&length) {
+                return Err(invalid("tuples must have between 1 and 7 cells"));
+            }
+            let mut tuple = vec![0; length];
+            reader.read_exact(&mut tuple)?;
+            if tuple.iter().any(|&cell| cell >= 16) {
+                return Err(invalid("tuple cells must be between 0 and 15"));
+            }
+            tuples.push(tuple);
+        }
+
+        let mut weights = Vec::with_capacity(tuples.len());
+        for tuple in &tuples {
+            let mut bytes = vec![0; 4 << (4 * tuple.len())];
+            reader.read_exact(&mut bytes)?;
+            weights.push(
+                bytes
+                    .chunks_exact(4)
+                    .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+                    .collect(),
+            );
+        }
+        Ok(Self::with_weights(
+            tuples,
+            weights,
+            alpha,
+            lambda,
+            games_trained,
+            rng,
+        ))
+    }
+
+    /// Writes the weights to `path`, through a temporary file so that an
+    /// interrupted save never leaves a broken checkpoint.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let temporary = path.with_extension("tmp");
+        self.write_to(File::create(&temporary)?)?;
+        fs::rename(temporary, path)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::read_from(File::open(path)?)
+    }
+}
+
+impl Strategy for NTupleNetwork {
+    fn name(&self) -> String {
+        "N-tuple".to_string()
+    }
+
+    fn choose(&mut self, game: &Game) -> Option<Direction> {
+        if game.game_over {
+            return None;
+        }
+        let board = Bitboard::from_game(game)?;
+        self.best_move(board).map(|(direction, _, _)| direction)
+    }
+}
+
+fn index(board: Bitboard, cells: &[u8]) -> usize {
+    cells
+        .iter()
+        .enumerate()
+        .map(|(j, &cell)| usize::from(board.rank(usize::from(cell))) << (4 * j))
+        .sum()
+}
+
+/// The tuple's cells under the eight rotations and reflections of the board.
+fn symmetries(tuple: &[u8]) -> Vec<Vec<u8>> {
+    let rotate = |cell: u8| {
+        let (row, col) = (cell / 4, cell % 4);
+        col * 4 + (3 - row)
+    };
+    let mirror = |cell: u8| {
+        let (row, col) = (cell / 4, cell % 4);
+        row * 4 + (3 - col)
+    };
+    let mut variants = Vec::with_capacity(8);
+    let mut cells = tuple.to_vec();
+    for _ in 0..4 {
+        variants.push(cells.clone());
+        variants.push(cells.iter().map(|&cell| mirror(cell)).collect());
+        cells = cells.iter().map(|&cell| rotate(cell)).collect();
+    }
+    variants
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A few games into training, so the weights aren't all zero.
+    fn trained() -> NTupleNetwork {
+        let mut network = NTupleNetwork::new(&[&[0, 1, 2], &[4, 5, 8, 9]], 0.1, 0.5, 7);
+        for _ in 0..3 {
+            network.train_game();
+        }
+        network
+    }
+
+    #[test]
+    fn weight_files_round_trip() {
+        let network = trained();
+        let mut bytes = Vec::new();
+        network.write_to(&mut bytes).unwrap();
+        assert_eq!(&bytes[..8], MAGIC);
+
+        let read = NTupleNetwork::read_from(bytes.as_slice()).unwrap();
+        assert_eq!(read, network);
+        assert_eq!(read.games_trained(), 3);
+        assert!(read.weights.iter().flatten().any(|&weight| weight != 0.0));
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let mut bytes = Vec::new();
+        trained().write_to(&mut bytes).unwrap();
+        // Cut inside the header, the tuples and the last weight
+        for length in [0, 5, 20, 45, bytes.len() - 1] {
+            let error = NTupleNetwork::read_from(&bytes[..length]).unwrap_err();
+            assert_eq!(
+                error.kind(),
+                io::ErrorKind::UnexpectedEof,
+                "cut at {length}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_other_files_and_versions() {
+        let mut bytes = Vec::new();
+        trained().write_to(&mut bytes).unwrap();
+
+        let mut other = bytes.clone();
+        other[0] = b'X';
+        let error = NTupleNetwork::read_from(other.as_slice()).unwrap_err();
+        assert_eq!(error.to_string(), "not an n-tuple weight file");
+
+        let mut newer = bytes.clone();
+        newer[8..12].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+        let error = NTupleNetwork::read_from(newer.as_slice()).unwrap_err();
+        assert_eq!(error.to_string(), "unsupported weight file version 2");
+
+        // The first tuple's first cell, after the 40-byte header and its length
+        let mut outside = bytes;
+        outside[44] = 16;
+        let error = NTupleNetwork::read_from(outside.as_slice()).unwrap_err();
+        assert_eq!(error.to_string(), "tuple cells must be between 0 and 15");
+    }
+}
@@ -0,0 +1,160 @@
+//! Colors of the board, shared by the game window and image export.
+
+use crate::game::Cell;
+use crate::rules::MergeRule;
+
+/// An opaque color with channels from 0 to 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rgb {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+}
+
+impl Rgb {
+    pub const WHITE: Rgb = Rgb::new(1.0, 1.0, 1.0);
+    pub const BLACK: Rgb = Rgb::new(0.0, 0.0, 0.0);
+
+    pub const fn new(r: f32, g: f32, b: f32) -> Self {
+        Rgb { r, g, b }
+    }
+
+    /// The color as `#rrggbb`.
+    pub fn hex(self) -> String {
+        let [r, g, b] = self.bytes();
+        format!("#{r:02x}{g:02x}{b:02x}")
+    }
+
+    pub fn bytes(self) -> [u8; 3] {
+        [self.r, self.g, self.b].map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+    }
+}
+
+/// Text color of the light and dark themes.
+pub fn text(dark_mode: bool) -> Rgb {
+    if dark_mode {
+        Rgb::new(0.9, 0.9, 0.9)
+    } else {
+        Rgb::BLACK
+    }
+}
+
+/// Window background of the light and dark themes.
+pub fn background(dark_mode: bool) -> Rgb {
+    if dark_mode {
+        Rgb::new(
+            0x20 as f32 / 255.0,
+            0x22 as f32 / 255.0,
+            0x25 as f32 / 255.0,
+        )
+    } else {
+        Rgb::WHITE
+    }
+}
+
+/// Background and text colors of a cell for the active theme.
+pub fn tile_colors(cell: Cell, rule: MergeRule, dark_mode: bool) -> (Rgb, Rgb) {
+    let tile_value = cell.value().unwrap_or(0);
+    // Other merge rules reuse the colors of the matching power of two
+    let palette_value = match tile_value {
+        0 => 0,
+        value => rule.palette_value(value),
+    };
+
+    if cell == Cell::Block {
+        // Blocks are drawn as solid slabs, distinct from any tile
+        let bg = if dark_mode {
+            Rgb::new(0.05, 0.05, 0.08)
+        } else {
+            Rgb::new(0.35, 0.38, 0.42)
+        };
+        (bg, Rgb::WHITE)
+    } else if rule == MergeRule::Threes && matches!(tile_value, 1 | 2) {
+        // Threes' 1s and 2s only combine with each other, so they get their own colors
+        let bg = if tile_value == 1 {
+            Rgb::new(0.4, 0.7, 1.0)
+        } else {
+            Rgb::new(1.0, 0.4, 0.5)
+        };
+        (bg, Rgb::WHITE)
+    } else if dark_mode {
+        // Dark mode colors
+        let bg = match palette_value {
+            0 => Rgb::new(0.2, 0.2, 0.2),
+            2 => Rgb::new(0.3, 0.3, 0.3),
+            4 => Rgb::new(0.4, 0.4, 0.35),
+            8 => Rgb::new(0.5, 0.4, 0.2),
+            16 => Rgb::new(0.6, 0.3, 0.2),
+            32 => Rgb::new(0.7, 0.2, 0.2),
+            64 => Rgb::new(0.8, 0.1, 0.1),
+            128 => Rgb::new(0.7, 0.6, 0.1),
+            256 => Rgb::new(0.8, 0.7, 0.1),
+            512 => Rgb::new(0.9, 0.8, 0.1),
+            1024 => Rgb::new(1.0, 0.9, 0.1),
+            2048 => Rgb::new(1.0, 0.8, 0.0),
+            4096 => Rgb::new(0.9, 0.1, 0.9),
+            8192 => Rgb::new(0.8, 0.2, 0.9),
+            16384 => Rgb::new(0.7, 0.3, 0.9),
+            32768 => Rgb::new(0.6, 0.4, 0.9),
+            65536 => Rgb::new(0.5, 0.5, 0.9),
+            131072 => Rgb::new(0.4, 0.6, 0.9),
+            262144 => Rgb::new(0.3, 0.7, 0.9),
+            524288 => Rgb::new(0.2, 0.8, 0.9),
+            1048576 => Rgb::new(0.1, 0.9, 0.9),
+            2097152 => Rgb::new(0.0, 1.0, 0.9),
+            4194304 => Rgb::new(0.0, 0.9, 1.0),
+            _ => Rgb::new(0.3, 0.6, 0.9),
+        };
+        let text_color = if palette_value <= 4 && palette_value > 0 {
+            Rgb::new(0.8, 0.8, 0.8)
+        } else {
+            Rgb::WHITE
+        };
+        (bg, text_color)
+    } else {
+        // Light mode colors (original)
+        let bg = match palette_value {
+            0 => Rgb::new(0.8, 0.8, 0.8),
+            2 => Rgb::new(0.9, 0.9, 0.85),
+            4 => Rgb::new(0.9, 0.85, 0.8),
+            8 => Rgb::new(0.9, 0.7, 0.5),
+            16 => Rgb::new(0.9, 0.6, 0.4),
+            32 => Rgb::new(0.9, 0.5, 0.3),
+            64 => Rgb::new(0.9, 0.4, 0.2),
+            128 => Rgb::new(0.9, 0.8, 0.4),
+            256 => Rgb::new(0.9, 0.8, 0.3),
+            512 => Rgb::new(0.9, 0.8, 0.2),
+            1024 => Rgb::new(0.9, 0.8, 0.1),
+            2048 => Rgb::new(1.0, 0.8, 0.0),
+            4096 => Rgb::new(0.8, 0.1, 0.8),
+            8192 => Rgb::new(0.7, 0.2, 0.8),
+            16384 => Rgb::new(0.6, 0.3, 0.8),
+            32768 => Rgb::new(0.5, 0.4, 0.8),
+            65536 => Rgb::new(0.4, 0.5, 0.8),
+            131072 => Rgb::new(0.3, 0.6, 0.8),
+            262144 => Rgb::new(0.2, 0.7, 0.8),
+            524288 => Rgb::new(0.1, 0.8, 0.8),
+            1048576 => Rgb::new(0.0, 0.9, 0.8),
+            2097152 => Rgb::new(0.0, 0.8, 0.9),
+            4194304 => Rgb::new(0.0, 0.7, 0.9),
+            _ => Rgb::new(0.2, 0.2, 0.2),
+        };
+        let text_color = if palette_value <= 4 && palette_value > 0 {
+            Rgb::new(0.4, 0.4, 0.4)
+        } else {
+            Rgb::WHITE
+        };
+        (bg, text_color)
+    }
+}
+
+/// Outline color of a cell for the active theme.
+pub fn border_color(cell: Cell, dark_mode: bool) -> Rgb {
+    if cell == Cell::Block {
+        Rgb::BLACK
+    } else if dark_mode {
+        Rgb::new(0.4, 0.4, 0.4)
+    } else {
+        Rgb::new(0.7, 0.7, 0.7)
+    }
+}
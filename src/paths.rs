@@ -0,0 +1,127 @@
+//! Where the game keeps its files.
+//!
+//! Data (the save, scores, puzzles, network weights) and config (settings)
+//! go in separate directories, by default the platform's own: on Linux
+//! `$XDG_DATA_HOME/iced-2048` and `$XDG_CONFIG_HOME/iced-2048`. In order of
+//! precedence, they can be moved by
+//!
+//! 1. `--data-dir` and `--config-dir` on the command line,
+//! 2. the `ICED_2048_DATA_DIR` and `ICED_2048_CONFIG_DIR` environment
+//!    variables,
+//! 3. portable mode, turned on with `--portable` or by a file named
+//!    `portable` next to the executable, which keeps them in `data` and
+//!    `config` beside it.
+//!
+//! A data directory given without a config directory holds the config too,
+//! so that one flag or variable is enough to isolate a test run.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+pub const DATA_DIR_VAR: &str = "ICED_2048_DATA_DIR";
+pub const CONFIG_DIR_VAR: &str = "ICED_2048_CONFIG_DIR";
+/// Name of the file next to the executable that turns on portable mode.
+pub const PORTABLE_MARKER: &str = "portable";
+
+const APP_FOLDER: &str = "iced-2048";
+
+static DIRS: OnceLock<Dirs> = OnceLock::new();
+
+/// Directories asked for on the command line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Overrides {
+    pub data_dir: Option<PathBuf>,
+    pub config_dir: Option<PathBuf>,
+    pub portable: bool,
+}
+
+/// The directories in use, or why there isn't one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dirs {
+    pub data: Result<PathBuf, String>,
+    pub config: Result<PathBuf, String>,
+    pub portable: bool,
+}
+
+impl Dirs {
+    fn resolve(overrides: Overrides) -> Self {
+        let env = |name| {
+            std::env::var_os(name)
+                .filter(|value| !value.is_empty())
+                .map(PathBuf::from)
+        };
+        let data = overrides.data_dir.or_else(|| env(DATA_DIR_VAR));
+        let config = overrides
+            .config_dir
+            .or_else(|| env(CONFIG_DIR_VAR))
+            .or_else(|| data.clone());
+        let portable = overrides.portable || executable_dir().join(PORTABLE_MARKER).is_file();
+        let pick = |chosen: Option<PathBuf>, portable_folder: &str, system: Option<PathBuf>| {
+            match chosen {
+                Some(path) => Ok(std::path::absolute(&path).unwrap_or(path)),
+                None if portable => Ok(executable_dir().join(portable_folder)),
+                None => system
+                    .map(|path| path.join(APP_FOLDER))
+                    .ok_or_else(|| format!("this system has no {portable_folder} directory")),
+            }
+        };
+        Dirs {
+            data: pick(data, "data", dirs::data_dir()),
+            config: pick(config, "config", dirs::config_dir()),
+            portable,
+        }
+    }
+}
+
+/// Sets the directories for the rest of the run. Only the first call
+/// counts, and only if no file has been looked up yet.
+pub fn init(overrides: Overrides) {
+    let _ = DIRS.set(Dirs::resolve(overrides));
+}
+
+/// The directories in use, from the environment unless [`init`] said
+/// otherwise.
+pub fn dirs() -> &'static Dirs {
+    DIRS.get_or_init(|| Dirs::resolve(Overrides::default()))
+}
+
+/// Path of a file in the data directory, creating the directory if needed.
+/// `None`, after a warning on stderr, if there's no directory to use.
+pub fn data_file_path(name: &str) -> Option<PathBuf> {
+    file_path(&dirs().data, name)
+}
+
+/// Path of a file in the config directory, creating the directory if
+/// needed, like [`data_file_path`].
+pub fn config_file_path(name: &str) -> Option<PathBuf> {
+    file_path(&dirs().config, name)
+}
+
+fn file_path(folder: &Result<PathBuf, String>, name: &str) -> Option<PathBuf> {
+    let path = folder.clone().and_then(|folder| {
+        std::fs::create_dir_all(&folder)
+            .map_err(|error| format!("can't create {}: {error}", folder.display()))?;
+        Ok(folder.join(name))
+    });
+    path.map_err(warn_once).ok()
+}
+
+/// Says once per problem that files aren't being kept, rather than failing
+/// quietly on every save.
+fn warn_once(error: String) {
+    static WARNED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    let mut warned = WARNED
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if !warned.contains(&error) {
+        eprintln!("Files won't be saved: {error}; use --data-dir or {DATA_DIR_VAR}");
+        warned.push(error);
+    }
+}
+
+fn executable_dir() -> PathBuf {
+    std::env::current_exe()
+        .ok()
+        .and_then(|path| path.parent().map(Path::to_path_buf))
+        .unwrap_or_default()
+}
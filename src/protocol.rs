@@ -0,0 +1,78 @@
+//! Wire protocol for two-player races through the relay server.
+//!
+//! Every message is a single line of JSON terminated by `\n`, with a `type`
+//! field naming the message. A session looks like this:
+//!
+//! 1. The client connects and sends `join` with its name and
+//!    [`PROTOCOL_VERSION`]. The server replies `welcome`.
+//! 2. Once a second client joins, the server pairs the two and sends each a
+//!    `start` carrying the shared spawn seed and the opponent's name.
+//! 3. After every move, a client sends an `update` with its board, score,
+//!    move count and the move it played. The server forwards it to the
+//!    opponent as `opponent`.
+//! 4. If a player disconnects, the server sends `opponent_left` to the other.
+//!
+//! Lines the server can't parse, or a `join` with the wrong version, are
+//! answered with `error`. Boards are rows of cells encoded like save files:
+//! `0` is empty, `-1` is a block and any other number is a tile.
+//!
+//! ```text
+//! > {"type":"join","name":"alice","version":1}
+//! < {"type":"welcome"}
+//! < {"type":"start","seed":1234,"opponent":"bob"}
+//! > {"type":"update","score":4,"moves":1,"board":[[4,0,0,0],...],"game_over":false,"won":false,"last_move":"left"}
+//! < {"type":"opponent","score":8,"moves":2,"board":[[0,0,0,8],...],"game_over":false,"won":false}
+//! < {"type":"opponent_left"}
+//! ```
+
+use crate::game::Direction;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Bumped whenever a message changes shape.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Address the relay listens on when none is given.
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:7878";
+
+/// A snapshot of one player's game.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerState {
+    pub score: u32,
+    pub moves: u32,
+    pub board: Vec<Vec<i64>>,
+    pub game_over: bool,
+    pub won: bool,
+    /// The move that led here, if any. Left out when there's none, and by
+    /// older clients.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_move: Option<Direction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Join { name: String, version: u32 },
+    Update(PlayerState),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Welcome,
+    Start { seed: u64, opponent: String },
+    Opponent(PlayerState),
+    OpponentLeft,
+    Error { message: String },
+}
+
+/// Serializes a message as one protocol line, including the newline.
+pub fn encode<T: Serialize>(message: &T) -> String {
+    let mut line = serde_json::to_string(message).expect("protocol messages always serialize");
+    line.push('\n');
+    line
+}
+
+pub fn decode<T: DeserializeOwned>(line: &str) -> Result<T, serde_json::Error> {
+    serde_json::from_str(line.trim_end())
+}
@@ -0,0 +1,319 @@
+//! Puzzles: hand-made positions with a fixed spawn sequence and an
+//! objective, shared as JSON puzzle packs.
+//!
+//! ```json
+//! {
+//!   "name": "Starter",
+//!   "author": "iced-2048",
+//!   "puzzles": [
+//!     {
+//!       "id": "chain",
+//!       "title": "Chain reaction",
+//!       "board": [[32, 16, 8, 8], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]],
+//!       "spawns": [{ "cell": 15, "value": 2 }, { "cell": 14, "value": 2 }],
+//!       "objective": { "type": "make_tile", "value": 64, "moves": 3 }
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! Boards are written as in save files: rows of tile values for square
+//! boards, with 0 for empty and -1 for a block, or `{"radius": r, "cells":
+//! [...]}` for hex boards. An optional `"rule"` picks the merge rule, such as
+//! `"Fibonacci"`; the standard rule is the default.
+//!
+//! After each move the next spawn is placed on its cell, numbered from 0 in
+//! reading order, or skipped if that cell is taken. Once the spawns run out
+//! no more tiles appear. Objectives are:
+//!
+//! | `type`      | Fields            | Solved when                          |
+//! |-------------|-------------------|--------------------------------------|
+//! | `make_tile` | `value`, `moves`  | a tile of at least `value` is made   |
+//! | `clear_to`  | `tiles`, `moves`  | at most `tiles` tiles are left       |
+//! | `score`     | `points`, `moves` | the score reaches `points`           |
+//!
+//! A puzzle fails once `moves` moves have been played without solving it,
+//! or when no move is left.
+
+use crate::board::Board;
+use crate::game::{Cell, Game, Mode, UndoPolicy, Variant};
+use crate::rules::MergeRule;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::Path;
+
+/// A tile placed by the puzzle instead of a random spawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Spawn {
+    pub cell: usize,
+    pub value: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Objective {
+    MakeTile { value: u32, moves: u32 },
+    ClearTo { tiles: usize, moves: u32 },
+    Score { points: u32, moves: u32 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Open,
+    Solved,
+    Failed,
+}
+
+impl Objective {
+    pub fn label(self) -> String {
+        match self {
+            Objective::MakeTile { value, moves } => format!("Make {value} in {moves} moves"),
+            Objective::ClearTo { tiles: 1, moves } => {
+                format!("Clear to a single tile in {moves} moves")
+            }
+            Objective::ClearTo { tiles, moves } => {
+                format!("Clear to {tiles} tiles in {moves} moves")
+            }
+            Objective::Score { points, moves } => format!("Score {points} in {moves} moves"),
+        }
+    }
+
+    /// The number of moves allowed.
+    pub fn moves(self) -> u32 {
+        match self {
+            Objective::MakeTile { moves, .. }
+            | Objective::ClearTo { moves, .. }
+            | Objective::Score { moves, .. } => moves,
+        }
+    }
+
+    /// Whether `game` has met the objective, or can no longer meet it.
+    pub fn status(self, game: &Game) -> Status {
+        let solved = match self {
+            Objective::MakeTile { value, .. } => game.max_tile() >= value,
+            Objective::ClearTo { tiles, .. } => {
+                game.board
+                    .cells()
+                    .iter()
+                    .filter(|cell| matches!(cell, Cell::Tile(_)))
+                    .count()
+                    <= tiles
+            }
+            Objective::Score { points, .. } => game.score >= points,
+        };
+        if solved {
+            Status::Solved
+        } else if game.moves >= self.moves() || game.game_over {
+            Status::Failed
+        } else {
+            Status::Open
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Puzzle {
+    /// Identifies the puzzle within its pack, for tracking which are solved.
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub rule: MergeRule,
+    pub board: Board,
+    #[serde(default)]
+    pub spawns: Vec<Spawn>,
+    pub objective: Objective,
+}
+
+impl Puzzle {
+    pub fn validate(&self) -> Result<(), String> {
+        let tiles: Vec<u32> = self
+            .board
+            .cells()
+            .iter()
+            .filter_map(|cell| cell.value())
+            .collect();
+        if tiles.is_empty() {
+            return Err(format!("puzzle {}: the board has no tiles", self.id));
+        }
+        if let Some(value) = tiles.iter().find(|&&value| !self.rule.is_tile(value)) {
+            return Err(format!(
+                "puzzle {}: {value} isn't a tile in {}",
+                self.id,
+                self.rule.label()
+            ));
+        }
+        let cells = self.board.cells().len();
+        if let Some(spawn) = self.spawns.iter().find(|spawn| spawn.cell >= cells) {
+            return Err(format!(
+                "puzzle {}: spawn cell {} is outside the board's {cells} cells",
+                self.id, spawn.cell
+            ));
+        }
+        if let Some(spawn) = self
+            .spawns
+            .iter()
+            .find(|spawn| !self.rule.is_tile(spawn.value))
+        {
+            return Err(format!(
+                "puzzle {}: spawned {} isn't a tile in {}",
+                self.id,
+                spawn.value,
+                self.rule.label()
+            ));
+        }
+        if self.objective.moves() == 0 {
+            return Err(format!("puzzle {}: the objective allows no moves", self.id));
+        }
+        Ok(())
+    }
+
+    /// A game set up at the puzzle's position. `pack` is the name of the
+    /// pack it comes from.
+    pub fn start(&self, pack: &str, dark_mode: bool) -> Game {
+        let variant = Variant {
+            rule: self.rule,
+            shape: self.board.shape(),
+            ..Variant::default()
+        };
+        let mut game = Game::build(
+            Mode::Puzzle(self.objective),
+            UndoPolicy::Disabled,
+            variant,
+            dark_mode,
+            None,
+        );
+        game.board = self.board.clone();
+        game.spawns = VecDeque::from(self.spawns.clone());
+        game.puzzle = Some(key(pack, &self.id));
+        game
+    }
+}
+
+/// A named collection of puzzles, as stored in a puzzle pack file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pack {
+    pub name: String,
+    #[serde(default)]
+    pub author: String,
+    pub puzzles: Vec<Puzzle>,
+}
+
+impl Pack {
+    pub fn parse(json: &str) -> Result<Self, String> {
+        let pack: Pack = serde_json::from_str(json).map_err(|error| error.to_string())?;
+        for puzzle in &pack.puzzles {
+            puzzle.validate()?;
+        }
+        Ok(pack)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path).map_err(|error| error.to_string())?;
+        Self::parse(&json)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|error| error.to_string())?;
+        std::fs::write(path, json).map_err(|error| error.to_string())
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Puzzle> {
+        self.puzzles.iter().find(|puzzle| puzzle.id == id)
+    }
+}
+
+/// Identifies a puzzle across packs, as `pack/id`.
+pub fn key(pack: &str, id: &str) -> String {
+    format!("{pack}/{id}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A pack of one puzzle, with `fields` replacing the puzzle's defaults.
+    fn pack_json(fields: &str) -> String {
+        let mut puzzle = serde_json::json!({
+            "id": "p",
+            "title": "Puzzle",
+            "board": [[2, 2, 0], [0, 0, 0], [0, 0, 0]],
+            "spawns": [{ "cell": 8, "value": 2 }],
+            "objective": { "type": "make_tile", "value": 4, "moves": 1 },
+        });
+        let fields: serde_json::Value = serde_json::from_str(fields).unwrap();
+        for (field, value) in fields.as_object().unwrap() {
+            puzzle[field] = value.clone();
+        }
+        serde_json::json!({ "name": "Test", "puzzles": [puzzle] }).to_string()
+    }
+
+    #[test]
+    fn parses_valid_packs() {
+        let pack = Pack::parse(&pack_json("{}")).unwrap();
+        assert_eq!(pack.name, "Test");
+        assert_eq!(pack.author, "");
+        let puzzle = pack.get("p").unwrap();
+        assert_eq!(puzzle.rule, MergeRule::Standard);
+        assert_eq!(puzzle.spawns, [Spawn { cell: 8, value: 2 }]);
+
+        let fibonacci =
+            pack_json(r#"{"rule": "Fibonacci", "board": [[1, 1, 0], [3, 0, 0], [0, 0, 0]]}"#);
+        assert_eq!(
+            Pack::parse(&fibonacci).unwrap().puzzles[0].rule,
+            MergeRule::Fibonacci
+        );
+    }
+
+    #[test]
+    fn parses_the_starter_pack() {
+        let pack = Pack::parse(include_str!("../puzzles/starter.json")).unwrap();
+        assert!(!pack.puzzles.is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_puzzles() {
+        let error = |fields: &str| Pack::parse(&pack_json(fields)).unwrap_err();
+        assert_eq!(
+            error(r#"{"board": [[0, 0, 0], [0, -1, 0], [0, 0, 0]]}"#),
+            "puzzle p: the board has no tiles"
+        );
+        assert_eq!(
+            error(r#"{"board": [[2, 3, 0], [0, 0, 0], [0, 0, 0]]}"#),
+            "puzzle p: 3 isn't a tile in Powers of 2"
+        );
+        assert_eq!(
+            error(r#"{"spawns": [{ "cell": 9, "value": 2 }]}"#),
+            "puzzle p: spawn cell 9 is outside the board's 9 cells"
+        );
+        assert_eq!(
+            error(r#"{"spawns": [{ "cell": 0, "value": 3 }]}"#),
+            "puzzle p: spawned 3 isn't a tile in Powers of 2"
+        );
+        assert_eq!(
+            error(r#"{"spawns": [{ "cell": 0, "value": 0 }]}"#),
+            "puzzle p: spawned 0 isn't a tile in Powers of 2"
+        );
+        assert_eq!(
+            error(r#"{"objective": { "type": "score", "points": 8, "moves": 0 }}"#),
+            "puzzle p: the objective allows no moves"
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_json() {
+        assert!(Pack::parse("not json").is_err());
+        // Rows of different lengths
+        let ragged = pack_json(r#"{"board": [[2, 2], [0, 0, 0], [0, 0, 0]]}"#);
+        assert!(
+            Pack::parse(&ragged)
+                .unwrap_err()
+                .contains("square board rows")
+        );
+        let unknown = pack_json(r#"{"objective": { "type": "win", "moves": 3 }}"#);
+        assert!(
+            Pack::parse(&unknown)
+                .unwrap_err()
+                .contains("unknown variant")
+        );
+    }
+}
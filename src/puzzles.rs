@@ -0,0 +1,159 @@
+use crate::Message;
+use crate::focus::focusable;
+use iced::widget::{button, column, container, row, scrollable, text};
+use iced::{Element, Length};
+use iced_2048::puzzle::{self, Pack, Puzzle};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+
+/// The pack that ships with the game.
+const STARTER_PACK: &str = include_str!("../puzzles/starter.json");
+
+/// Every puzzle pack found, and which puzzles have been solved.
+#[derive(Debug, Clone, Default)]
+pub struct PuzzleLibrary {
+    pub packs: Vec<Pack>,
+    /// Pack files that couldn't be read, with the reason.
+    pub errors: Vec<String>,
+    /// Where players put their own puzzle packs.
+    pub folder: Option<PathBuf>,
+    solved: BTreeSet<String>,
+}
+
+impl PuzzleLibrary {
+    /// Loads the starter pack, then every `.json` pack in the `puzzles`
+    /// folder of the data directory.
+    pub fn load() -> Self {
+        let mut library = PuzzleLibrary {
+            solved: iced_2048::data_file_path("puzzles-solved.json")
+                .and_then(|path| fs::read_to_string(path).ok())
+                .and_then(|contents| serde_json::from_str(&contents).ok())
+                .unwrap_or_default(),
+            folder: packs_folder(),
+            ..PuzzleLibrary::default()
+        };
+        match Pack::parse(STARTER_PACK) {
+            Ok(pack) => library.packs.push(pack),
+            Err(error) => library.errors.push(format!("starter pack: {error}")),
+        }
+
+        let mut paths: Vec<PathBuf> = library
+            .folder
+            .as_ref()
+            .and_then(|folder| fs::read_dir(folder).ok())
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "json")
+            })
+            .collect();
+        paths.sort();
+        for path in paths {
+            match Pack::load(&path) {
+                Ok(pack) => library.packs.push(pack),
+                Err(error) => library.errors.push(format!("{}: {error}", path.display())),
+            }
+        }
+        library
+    }
+
+    pub fn save(&self) {
+        if let Some(path) = iced_2048::data_file_path("puzzles-solved.json")
+            && let Ok(json) = serde_json::to_string_pretty(&self.solved)
+        {
+            let _ = fs::write(path, json);
+        }
+    }
+
+    pub fn is_solved(&self, key: &str) -> bool {
+        self.solved.contains(key)
+    }
+
+    pub fn solved_count(&self) -> usize {
+        self.solved.len()
+    }
+
+    /// Records a solved puzzle, returning whether it's the first time.
+    pub fn mark_solved(&mut self, key: &str) -> bool {
+        self.solved.insert(key.to_string())
+    }
+
+    /// The pack name and puzzle for a key from [`puzzle::key`].
+    pub fn find(&self, key: &str) -> Option<(&str, &Puzzle)> {
+        self.packs.iter().find_map(|pack| {
+            pack.puzzles
+                .iter()
+                .find(|puzzle| puzzle::key(&pack.name, &puzzle.id) == key)
+                .map(|puzzle| (pack.name.as_str(), puzzle))
+        })
+    }
+}
+
+fn packs_folder() -> Option<PathBuf> {
+    let folder = iced_2048::data_file_path("puzzles")?;
+    fs::create_dir_all(&folder).ok()?;
+    Some(folder)
+}
+
+pub fn view_puzzles(library: &PuzzleLibrary) -> Element<'_, Message> {
+    let title = text("Puzzles").size(40);
+
+    let mut packs = column![].spacing(24);
+    for pack in &library.packs {
+        let solved = pack
+            .puzzles
+            .iter()
+            .filter(|puzzle| library.is_solved(&puzzle::key(&pack.name, &puzzle.id)))
+            .count();
+        let heading = match pack.author.as_str() {
+            "" => format!("{} ({solved}/{} solved)", pack.name, pack.puzzles.len()),
+            author => format!(
+                "{} by {author} ({solved}/{} solved)",
+                pack.name,
+                pack.puzzles.len()
+            ),
+        };
+        let rows = pack.puzzles.iter().map(|puzzle| {
+            let key = puzzle::key(&pack.name, &puzzle.id);
+            let mark = if library.is_solved(&key) { "✓" } else { "" };
+            row![
+                text(mark).size(18).width(20),
+                text(&puzzle.title).size(18).width(200),
+                text(puzzle.objective.label()).size(16).width(300),
+                focusable(button("Play").padding(8), Message::StartPuzzle(key)),
+            ]
+            .spacing(12)
+            .align_y(iced::Alignment::Center)
+            .into()
+        });
+        packs = packs.push(column![text(heading).size(22)].extend(rows).spacing(8));
+    }
+    for error in &library.errors {
+        packs = packs.push(text(format!("Couldn't load {error}")).size(14));
+    }
+
+    let folder_hint = match &library.folder {
+        Some(folder) => format!("Add your own packs to {}", folder.display()),
+        None => String::new(),
+    };
+
+    let content = column![
+        title,
+        scrollable(packs).height(Length::Fill),
+        text(folder_hint).size(14),
+        focusable(button("Back").padding(12), Message::ShowModes),
+    ]
+    .spacing(20)
+    .align_x(iced::Alignment::Center);
+
+    container(content)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center(Length::Fill)
+        .padding(20)
+        .into()
+}
@@ -0,0 +1,396 @@
+use crate::focus::focusable;
+use crate::versus::Outcome;
+use crate::{Message, key_direction, tile_colors, view_board};
+use iced::futures::SinkExt;
+use iced::futures::channel::mpsc;
+use iced::keyboard;
+use iced::widget::{button, column, container, row, text, text_input};
+use iced::{Element, Length, Subscription, Theme};
+use iced_2048::game::{Cell, Game, Variant};
+use iced_2048::protocol::{
+    ClientMessage, DEFAULT_ADDRESS, PROTOCOL_VERSION, PlayerState, ServerMessage, decode, encode,
+};
+use std::io;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc as tokio_mpsc;
+
+/// Side length of a cell in the opponent's miniature board.
+const MINI_CELL: f32 = 28.0;
+
+/// Events from the connection to the relay server.
+#[derive(Debug, Clone)]
+pub enum Event {
+    Connected(Connection),
+    Server(ServerMessage),
+    Disconnected(String),
+}
+
+/// Sends messages to the relay over an open connection.
+#[derive(Debug, Clone)]
+pub struct Connection(tokio_mpsc::UnboundedSender<ClientMessage>);
+
+impl Connection {
+    fn send(&self, message: ClientMessage) {
+        let _ = self.0.send(message);
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Phase {
+    /// Not connected; the player is editing the address and name.
+    Lobby,
+    /// Connecting, or connected and waiting to be paired.
+    Waiting,
+    Playing {
+        game: Box<Game>,
+        opponent_name: String,
+        opponent: Option<PlayerState>,
+        opponent_left: bool,
+        outcome: Option<Outcome>,
+    },
+}
+
+/// A race against another player over a relay server. Both players get the
+/// same seed from the relay and see each other's board as they play.
+#[derive(Debug, Clone)]
+pub struct Race {
+    pub address: String,
+    pub name: String,
+    phase: Phase,
+    connection: Option<Connection>,
+    /// Last connection problem or server error, shown in the lobby.
+    status: Option<String>,
+    dark_mode: bool,
+}
+
+impl Race {
+    pub fn new(dark_mode: bool) -> Self {
+        Race {
+            address: DEFAULT_ADDRESS.to_string(),
+            name: "Player".to_string(),
+            phase: Phase::Lobby,
+            connection: None,
+            status: None,
+            dark_mode,
+        }
+    }
+
+    pub fn set_dark_mode(&mut self, dark_mode: bool) {
+        self.dark_mode = dark_mode;
+        if let Phase::Playing { game, .. } = &mut self.phase {
+            game.dark_mode = dark_mode;
+        }
+    }
+
+    pub fn connect(&mut self) {
+        self.status = None;
+        self.phase = Phase::Waiting;
+    }
+
+    /// Returns to the lobby, which drops the connection.
+    pub fn leave(&mut self) {
+        self.phase = Phase::Lobby;
+        self.connection = None;
+    }
+
+    pub fn handle(&mut self, event: Event) {
+        match event {
+            Event::Connected(connection) => self.connection = Some(connection),
+            Event::Disconnected(reason) => {
+                self.leave();
+                self.status = Some(reason);
+            }
+            Event::Server(message) => self.handle_server(message),
+        }
+    }
+
+    fn handle_server(&mut self, message: ServerMessage) {
+        match message {
+            ServerMessage::Welcome => {}
+            ServerMessage::Start { seed, opponent } => {
+                let game = Box::new(Game::new_seeded(seed, Variant::default(), self.dark_mode));
+                self.phase = Phase::Playing {
+                    game,
+                    opponent_name: opponent,
+                    opponent: None,
+                    opponent_left: false,
+                    outcome: None,
+                };
+                self.send_update();
+            }
+            ServerMessage::Opponent(state) => {
+                if let Phase::Playing {
+                    game,
+                    opponent,
+                    outcome,
+                    ..
+                } = &mut self.phase
+                {
+                    *opponent = Some(state);
+                    *outcome = outcome.or_else(|| decide(game, opponent.as_ref()));
+                }
+            }
+            ServerMessage::OpponentLeft => {
+                if let Phase::Playing { opponent_left, .. } = &mut self.phase {
+                    *opponent_left = true;
+                }
+            }
+            ServerMessage::Error { message } => {
+                self.leave();
+                self.status = Some(message);
+            }
+        }
+    }
+
+    pub fn key_pressed(&mut self, key: &keyboard::Key) {
+        let Phase::Playing {
+            game,
+            opponent,
+            outcome,
+            ..
+        } = &mut self.phase
+        else {
+            return;
+        };
+        if outcome.is_some() || game.game_over {
+            return;
+        }
+        if let Some(direction) = key_direction(game.board.shape(), key)
+            && game.move_tiles(direction)
+        {
+            *outcome = decide(game, opponent.as_ref());
+            self.send_update();
+        }
+    }
+
+    fn send_update(&self) {
+        if let (Phase::Playing { game, .. }, Some(connection)) = (&self.phase, &self.connection) {
+            connection.send(ClientMessage::Update(game.player_state()));
+        }
+    }
+
+    pub fn subscription(&self) -> Subscription<Event> {
+        match self.phase {
+            Phase::Lobby => Subscription::none(),
+            Phase::Waiting | Phase::Playing { .. } => {
+                connect(self.address.clone(), self.name.clone())
+            }
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let title = text("Race").size(50);
+
+        let content = match &self.phase {
+            Phase::Lobby => {
+                let mut lobby = column![
+                    text("Start a relay with `iced-2048-relay`, then connect both players to it.")
+                        .size(16),
+                    row![
+                        text("Server").size(16).width(70),
+                        text_input(DEFAULT_ADDRESS, &self.address)
+                            .on_input(Message::RaceAddressChanged)
+                            .width(240),
+                    ]
+                    .spacing(12)
+                    .align_y(iced::Alignment::Center),
+                    row![
+                        text("Name").size(16).width(70),
+                        text_input("Player", &self.name)
+                            .on_input(Message::RaceNameChanged)
+                            .on_submit(Message::ConnectRace)
+                            .width(240),
+                    ]
+                    .spacing(12)
+                    .align_y(iced::Alignment::Center),
+                ]
+                .spacing(12)
+                .align_x(iced::Alignment::Center);
+                if let Some(status) = &self.status {
+                    lobby = lobby.push(text(status).size(16));
+                }
+                lobby.push(
+                    row![
+                        focusable(button("Connect").padding(12), Message::ConnectRace),
+                        focusable(button("Back").padding(12), Message::ShowModes),
+                    ]
+                    .spacing(12),
+                )
+            }
+            Phase::Waiting => column![
+                text(format!("Waiting for an opponent on {}…", self.address)).size(18),
+                focusable(button("Cancel").padding(12), Message::LeaveRace),
+            ]
+            .spacing(20)
+            .align_x(iced::Alignment::Center),
+            Phase::Playing {
+                game,
+                opponent_name,
+                opponent,
+                opponent_left,
+                outcome,
+            } => {
+                let own = column![
+                    text(format!("{} • Score: {}", self.name, game.score)).size(20),
+                    view_board(&game.board, game.variant.rule, game.dark_mode),
+                ]
+                .spacing(12)
+                .align_x(iced::Alignment::Center);
+
+                let opponent_status = match opponent {
+                    _ if *opponent_left => format!("{opponent_name} • Left"),
+                    Some(state) => format!(
+                        "{opponent_name} • Score: {} • Moves: {}",
+                        state.score, state.moves
+                    ),
+                    None => opponent_name.clone(),
+                };
+                let mini: Element<Message> = match opponent {
+                    Some(state) => view_mini_board(&state.board, self.dark_mode),
+                    None => text("Waiting for their first move").size(14).into(),
+                };
+                let other = column![text(opponent_status).size(16), mini]
+                    .spacing(12)
+                    .align_x(iced::Alignment::Center);
+
+                let target = game.variant.rule.win_target();
+                let status = match outcome {
+                    Some(Outcome::Winner(0)) => "You win!".to_string(),
+                    Some(Outcome::Winner(_)) => format!("{opponent_name} wins!"),
+                    Some(Outcome::Draw) => "It's a draw!".to_string(),
+                    None if *opponent_left => "Your opponent left the race".to_string(),
+                    None => format!(
+                        "First to {target} wins, or the higher score once both boards are stuck"
+                    ),
+                };
+
+                column![
+                    row![own, other]
+                        .spacing(40)
+                        .align_y(iced::Alignment::Center),
+                    text(status).size(18),
+                    focusable(button("Leave").padding(12), Message::LeaveRace),
+                ]
+                .spacing(20)
+                .align_x(iced::Alignment::Center)
+            }
+        };
+
+        container(
+            column![title, content]
+                .spacing(20)
+                .align_x(iced::Alignment::Center),
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center(Length::Fill)
+        .padding(20)
+        .into()
+    }
+}
+
+/// Same rule as hot-seat versus, with the local player as player 0.
+fn decide(game: &Game, opponent: Option<&PlayerState>) -> Option<Outcome> {
+    if game.won {
+        return Some(Outcome::Winner(0));
+    }
+    let opponent = opponent?;
+    if opponent.won {
+        return Some(Outcome::Winner(1));
+    }
+    if !(game.game_over && opponent.game_over) {
+        return None;
+    }
+    Some(match game.score.cmp(&opponent.score) {
+        std::cmp::Ordering::Greater => Outcome::Winner(0),
+        std::cmp::Ordering::Less => Outcome::Winner(1),
+        std::cmp::Ordering::Equal => Outcome::Draw,
+    })
+}
+
+/// A small, label-free rendering of the opponent's board.
+fn view_mini_board(board: &[Vec<i64>], dark_mode: bool) -> Element<'_, Message> {
+    let rows = board.iter().map(|cells| {
+        row(cells.iter().map(|&value| {
+            let cell = Cell::try_from(value).unwrap_or_default();
+            let (background_color, text_color) =
+                tile_colors(cell, Variant::default().rule, dark_mode);
+            let label = cell
+                .value()
+                .map(|value| value.to_string())
+                .unwrap_or_default();
+            container(text(label).size(10).color(text_color))
+                .width(MINI_CELL)
+                .height(MINI_CELL)
+                .align_x(iced::Alignment::Center)
+                .align_y(iced::Alignment::Center)
+                .style(move |_theme: &Theme| container::Style {
+                    background: Some(iced::Background::Color(background_color)),
+                    border: iced::Border {
+                        radius: 4.0.into(),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .into()
+        }))
+        .spacing(3)
+        .into()
+    });
+    column(rows).spacing(3).into()
+}
+
+/// Keeps a connection to the relay open for as long as the subscription is.
+fn connect(address: String, name: String) -> Subscription<Event> {
+    let id = (address.clone(), name.clone());
+    Subscription::run_with_id(
+        id,
+        iced::stream::channel(100, move |mut output| async move {
+            let reason = match run_connection(&address, name, &mut output).await {
+                Ok(()) => "The relay closed the connection".to_string(),
+                Err(error) => format!("Connection to {address} failed: {error}"),
+            };
+            let _ = output.send(Event::Disconnected(reason)).await;
+        }),
+    )
+}
+
+async fn run_connection(
+    address: &str,
+    name: String,
+    output: &mut mpsc::Sender<Event>,
+) -> io::Result<()> {
+    let stream = TcpStream::connect(address).await?;
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    let join = ClientMessage::Join {
+        name,
+        version: PROTOCOL_VERSION,
+    };
+    writer.write_all(encode(&join).as_bytes()).await?;
+
+    let (sender, mut outgoing) = tokio_mpsc::unbounded_channel();
+    let _ = output.send(Event::Connected(Connection(sender))).await;
+
+    loop {
+        tokio::select! {
+            line = lines.next_line() => {
+                let Some(line) = line? else {
+                    return Ok(());
+                };
+                let message = decode(&line)
+                    .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+                let _ = output.send(Event::Server(message)).await;
+            }
+            message = outgoing.recv() => {
+                // The sender is dropped when the player leaves the race
+                let Some(message) = message else {
+                    return Ok(());
+                };
+                writer.write_all(encode(&message).as_bytes()).await?;
+            }
+        }
+    }
+}
@@ -0,0 +1,155 @@
+//! A relay server that pairs race clients and forwards their updates.
+//!
+//! Clients are paired in the order they join. Each pair gets a fresh random
+//! seed so both players face the same spawns. See [`crate::protocol`] for
+//! the messages exchanged.
+
+use crate::protocol::{ClientMessage, PROTOCOL_VERSION, ServerMessage, decode, encode};
+use rand::Rng;
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+type ClientId = u64;
+
+/// Shared state of every connected client.
+#[derive(Default)]
+struct Lobby {
+    next_id: ClientId,
+    /// Write halves of every connection, by client.
+    streams: HashMap<ClientId, TcpStream>,
+    /// A client that has joined and is waiting for an opponent.
+    waiting: Option<(ClientId, String)>,
+    /// Both directions of every running match.
+    opponents: HashMap<ClientId, ClientId>,
+}
+
+impl Lobby {
+    fn send(&mut self, id: ClientId, message: &ServerMessage) {
+        if let Some(stream) = self.streams.get_mut(&id) {
+            let _ = stream.write_all(encode(message).as_bytes());
+        }
+    }
+
+    fn join(&mut self, id: ClientId, name: String) {
+        self.send(id, &ServerMessage::Welcome);
+        match self.waiting.take() {
+            Some((other, other_name)) if other != id => {
+                let seed = rand::rng().random();
+                self.opponents.insert(id, other);
+                self.opponents.insert(other, id);
+                self.send(
+                    id,
+                    &ServerMessage::Start {
+                        seed,
+                        opponent: other_name,
+                    },
+                );
+                self.send(
+                    other,
+                    &ServerMessage::Start {
+                        seed,
+                        opponent: name,
+                    },
+                );
+            }
+            _ => self.waiting = Some((id, name)),
+        }
+    }
+
+    fn leave(&mut self, id: ClientId) {
+        self.streams.remove(&id);
+        if self
+            .waiting
+            .as_ref()
+            .is_some_and(|(waiting, _)| *waiting == id)
+        {
+            self.waiting = None;
+        }
+        if let Some(other) = self.opponents.remove(&id) {
+            self.opponents.remove(&other);
+            self.send(other, &ServerMessage::OpponentLeft);
+        }
+    }
+}
+
+/// How long to wait after a failed accept before the next.
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Accepts clients on `listener`, serving each on its own thread. A client
+/// that can't be accepted is logged and skipped, so it doesn't end the
+/// races already running.
+pub fn serve(listener: TcpListener) -> io::Result<()> {
+    let lobby = Arc::new(Mutex::new(Lobby::default()));
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(error) => {
+                eprintln!("Couldn't accept a client: {error}");
+                // Out of file descriptors, say; give some time to close
+                thread::sleep(ACCEPT_RETRY_DELAY);
+                continue;
+            }
+        };
+        let lobby = Arc::clone(&lobby);
+        thread::spawn(move || {
+            let _ = handle_client(stream, &lobby);
+        });
+    }
+    Ok(())
+}
+
+fn handle_client(stream: TcpStream, lobby: &Mutex<Lobby>) -> io::Result<()> {
+    let writer = stream.try_clone()?;
+    let id = {
+        let mut lobby = lobby.lock().unwrap();
+        let id = lobby.next_id;
+        lobby.next_id += 1;
+        lobby.streams.insert(id, writer);
+        id
+    };
+
+    let result = read_messages(id, stream, lobby);
+    lobby.lock().unwrap().leave(id);
+    result
+}
+
+fn read_messages(id: ClientId, stream: TcpStream, lobby: &Mutex<Lobby>) -> io::Result<()> {
+    let mut joined = false;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut lobby = lobby.lock().unwrap();
+        match decode::<ClientMessage>(&line) {
+            Ok(ClientMessage::Join { version, .. }) if version != PROTOCOL_VERSION => {
+                let message = format!(
+                    "protocol version {version} is not supported, expected {PROTOCOL_VERSION}"
+                );
+                lobby.send(id, &ServerMessage::Error { message });
+            }
+            Ok(ClientMessage::Join { .. }) if joined => {
+                let message = "already joined".to_string();
+                lobby.send(id, &ServerMessage::Error { message });
+            }
+            Ok(ClientMessage::Join { name, .. }) => {
+                joined = true;
+                lobby.join(id, name);
+            }
+            Ok(ClientMessage::Update(state)) => {
+                if let Some(&other) = lobby.opponents.get(&id) {
+                    lobby.send(other, &ServerMessage::Opponent(state));
+                }
+            }
+            Err(error) => {
+                let message = format!("invalid message: {error}");
+                lobby.send(id, &ServerMessage::Error { message });
+            }
+        }
+    }
+    Ok(())
+}
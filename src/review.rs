@@ -0,0 +1,258 @@
+use crate::focus::{focusable, focusable_maybe};
+use crate::{Message, view_board};
+use iced::futures::channel::mpsc;
+use iced::keyboard;
+use iced::widget::{button, column, container, row, scrollable, stack, text};
+use iced::{Color, Element, Length, Task, Theme, task};
+use iced_2048::analysis::{self, Quality, Review};
+use iced_2048::game::{Direction, Game, Turn};
+
+/// Width of a move's mark on the timeline.
+const MARK_WIDTH: f32 = 12.0;
+
+/// A move reviewed by the analysis thread.
+#[derive(Debug, Clone)]
+pub struct Event(Review);
+
+/// A finished game with its moves graded by the engine, like a chess
+/// engine's game review. Moves are analyzed on a background thread and
+/// appear on the timeline as they're done.
+pub struct GameReview {
+    game: Game,
+    turns: Vec<Turn>,
+    reviews: Vec<Review>,
+    selected: usize,
+    /// Stops the analysis when the review is dropped.
+    _analysis: task::Handle,
+}
+
+impl GameReview {
+    pub fn start(game: &Game) -> (Self, Task<Event>) {
+        let (sender, receiver) = mpsc::unbounded();
+        let analyzed = game.clone();
+        std::thread::spawn(move || {
+            let mut engine = analysis::engine();
+            analysis::analyze(&analyzed, &mut engine, |review| {
+                sender.unbounded_send(Event(review.clone())).is_ok()
+            });
+        });
+        let (task, handle) = Task::stream(receiver).abortable();
+        let review = GameReview {
+            game: game.clone(),
+            turns: game.turns(),
+            reviews: Vec::new(),
+            selected: 0,
+            _analysis: handle.abort_on_drop(),
+        };
+        (review, task)
+    }
+
+    /// Whether this is the review of `game`.
+    pub fn is_of(&self, game: &Game) -> bool {
+        self.game == *game
+    }
+
+    pub fn handle(&mut self, Event(review): Event) {
+        self.reviews.push(review);
+    }
+
+    pub fn select(&mut self, turn: usize) {
+        self.selected = turn.min(self.turns.len().saturating_sub(1));
+    }
+
+    /// Left and right step through the moves; Home and End jump to the ends.
+    pub fn key_pressed(&mut self, key: &keyboard::Key) {
+        use keyboard::key::Named;
+
+        match key {
+            keyboard::Key::Named(Named::ArrowLeft) => self.select(self.selected.saturating_sub(1)),
+            keyboard::Key::Named(Named::ArrowRight) => self.select(self.selected + 1),
+            keyboard::Key::Named(Named::Home) => self.select(0),
+            keyboard::Key::Named(Named::End) => self.select(self.turns.len()),
+            _ => {}
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let title = text("Game Review").size(40);
+
+        let Some(turn) = self.turns.get(self.selected) else {
+            return column![
+                title,
+                text("There are no recorded moves to review."),
+                focusable(button("Back").padding(12), Message::ShowGame),
+            ]
+            .spacing(20)
+            .align_x(iced::Alignment::Center)
+            .into();
+        };
+        let review = self.reviews.get(self.selected);
+        let dark_mode = self.game.dark_mode;
+
+        let board = view_board(&turn.board, self.game.variant.rule, dark_mode);
+        // The engine's move is drawn over the board
+        let overlay: Element<Message> = match review {
+            Some(review) => container(
+                text(arrow(review.best))
+                    .size(160)
+                    .color(Color::from_rgba(0.1, 0.6, 0.2, 0.7)),
+            )
+            .center(Length::Fill)
+            .into(),
+            None => container(text("")).into(),
+        };
+        let board = stack![board, overlay];
+
+        let mut details = column![
+            text(format!(
+                "Move {} of {} • Score {}",
+                self.selected + 1,
+                self.turns.len(),
+                turn.score
+            ))
+            .size(20),
+            text(format!("Played {}", turn.direction.label())).size(18),
+        ]
+        .spacing(8);
+        match review {
+            Some(review) => {
+                details = details
+                    .push(
+                        text(review.quality.label())
+                            .size(24)
+                            .color(quality_color(review.quality)),
+                    )
+                    .push(text(format!("Engine prefers {}", review.best.label())).size(18));
+                if review.values.len() > 1 {
+                    for (direction, value) in &review.values {
+                        details = details.push(
+                            text(format!("{:<10} {value:>8.0}", direction.label()))
+                                .font(iced::Font::MONOSPACE)
+                                .size(16),
+                        );
+                    }
+                } else {
+                    details = details.push(text("The only legal move").size(16));
+                }
+            }
+            None => details = details.push(text("Not analyzed yet").size(18)),
+        }
+        details = details.push(text(self.summary()).size(16));
+
+        let timeline = row(self.turns.iter().enumerate().map(|(i, _)| {
+            let color = self
+                .reviews
+                .get(i)
+                .map_or(Color::from_rgb(0.5, 0.5, 0.5), |review| {
+                    quality_color(review.quality)
+                });
+            let selected = i == self.selected;
+            button(text(""))
+                .width(MARK_WIDTH)
+                .height(if selected { 36 } else { 24 })
+                .padding(0)
+                .style(move |_theme: &Theme, _status| button::Style {
+                    background: Some(iced::Background::Color(color)),
+                    border: iced::Border {
+                        color: if selected {
+                            Color::BLACK
+                        } else {
+                            Color::TRANSPARENT
+                        },
+                        width: 2.0,
+                        radius: 2.0.into(),
+                    },
+                    ..Default::default()
+                })
+                .on_press(Message::SelectTurn(i))
+                .into()
+        }))
+        .spacing(2)
+        .align_y(iced::Alignment::End);
+        let timeline = scrollable(container(timeline).padding(4))
+            .direction(scrollable::Direction::Horizontal(
+                scrollable::Scrollbar::new(),
+            ))
+            .width(Length::Fill);
+
+        // The marks above are for the mouse; the keyboard steps with these
+        // or the arrow keys
+        let previous = focusable_maybe(
+            button("Previous").padding(12),
+            (self.selected > 0).then(|| Message::SelectTurn(self.selected - 1)),
+        );
+        let next = focusable_maybe(
+            button("Next").padding(12),
+            (self.selected + 1 < self.turns.len()).then(|| Message::SelectTurn(self.selected + 1)),
+        );
+        let button_row = row![
+            previous,
+            next,
+            focusable(button("Back").padding(12), Message::ShowGame)
+        ]
+        .spacing(12);
+
+        let content = column![
+            title,
+            row![board, details].spacing(40),
+            timeline,
+            text("Left/right arrows step through the moves").size(14),
+            button_row,
+        ]
+        .spacing(20)
+        .align_x(iced::Alignment::Center);
+
+        container(content)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .center(Length::Fill)
+            .padding(20)
+            .into()
+    }
+
+    /// Progress while analyzing, then how many moves got each grade.
+    fn summary(&self) -> String {
+        if self.reviews.len() < self.turns.len() {
+            return format!("Analyzing… {}/{}", self.reviews.len(), self.turns.len());
+        }
+        [
+            Quality::Best,
+            Quality::Good,
+            Quality::Inaccuracy,
+            Quality::Blunder,
+        ]
+        .iter()
+        .map(|&quality| {
+            let count = self
+                .reviews
+                .iter()
+                .filter(|review| review.quality == quality)
+                .count();
+            format!("{} {count}", quality.label())
+        })
+        .collect::<Vec<_>>()
+        .join(" • ")
+    }
+}
+
+fn quality_color(quality: Quality) -> Color {
+    match quality {
+        Quality::Best => Color::from_rgb(0.2, 0.7, 0.3),
+        Quality::Good => Color::from_rgb(0.4, 0.6, 0.9),
+        Quality::Inaccuracy => Color::from_rgb(0.95, 0.65, 0.1),
+        Quality::Blunder => Color::from_rgb(0.9, 0.2, 0.2),
+    }
+}
+
+fn arrow(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Up => "↑",
+        Direction::Down => "↓",
+        Direction::Left => "←",
+        Direction::Right => "→",
+        Direction::UpLeft => "↖",
+        Direction::UpRight => "↗",
+        Direction::DownLeft => "↙",
+        Direction::DownRight => "↘",
+    }
+}
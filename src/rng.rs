@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+/// Deterministic random source for seeded games.
+///
+/// This is SplitMix64, kept in-tree so that a seed produces the same spawn
+/// sequence on every machine regardless of `rand` version or platform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpawnRng {
+    state: u64,
+}
+
+impl SpawnRng {
+    pub fn new(seed: u64) -> Self {
+        SpawnRng { state: seed }
+    }
+
+    /// The current state, from which [`SpawnRng::new`] continues the same
+    /// sequence.
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a value in `0..n`. `n` must be non-zero.
+    pub fn below(&mut self, n: usize) -> usize {
+        ((self.next_u64() as u128 * n as u128) >> 64) as usize
+    }
+
+    /// Returns `true` with probability `p`.
+    pub fn chance(&mut self, p: f64) -> bool {
+        let unit = (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+        unit < p
+    }
+}
@@ -0,0 +1,224 @@
+use serde::{Deserialize, Serialize};
+
+/// How tiles combine when they slide into each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum MergeRule {
+    /// Two equal tiles merge into their sum.
+    #[default]
+    Standard,
+    /// Two adjacent Fibonacci numbers merge into the next one.
+    Fibonacci,
+    /// 1 and 2 merge into 3, then equal multiples of 3 merge.
+    Threes,
+    /// Three equal tiles merge into their sum.
+    Base3,
+}
+
+pub const MERGE_RULES: [MergeRule; 4] = [
+    MergeRule::Standard,
+    MergeRule::Fibonacci,
+    MergeRule::Threes,
+    MergeRule::Base3,
+];
+
+impl MergeRule {
+    pub fn label(self) -> String {
+        match self {
+            MergeRule::Standard => "Powers of 2",
+            MergeRule::Fibonacci => "Fibonacci",
+            MergeRule::Threes => "Threes",
+            MergeRule::Base3 => "Base 3",
+        }
+        .to_string()
+    }
+
+    /// Tile value that counts as a win.
+    pub fn win_target(self) -> u32 {
+        match self {
+            MergeRule::Standard => 2048,
+            MergeRule::Fibonacci => 2584,
+            MergeRule::Threes => 768,
+            MergeRule::Base3 => 2187,
+        }
+    }
+
+    /// Every tile value this rule can make, smallest first, up to the
+    /// largest that fits in a `u32`.
+    pub fn tiles(self) -> impl Iterator<Item = u32> {
+        let first: u32 = match self {
+            MergeRule::Standard => 2,
+            MergeRule::Fibonacci | MergeRule::Threes => 1,
+            MergeRule::Base3 => 3,
+        };
+        std::iter::successors(Some((first, 1u32)), move |&(value, previous)| {
+            let next = match self {
+                MergeRule::Standard => value.checked_mul(2),
+                MergeRule::Fibonacci => value.checked_add(previous),
+                MergeRule::Threes if value < 3 => Some(value + 1),
+                MergeRule::Threes => value.checked_mul(2),
+                MergeRule::Base3 => value.checked_mul(3),
+            }?;
+            Some((next, value))
+        })
+        .map(|(value, _)| value)
+    }
+
+    /// Whether a tile of `value` can appear under this rule.
+    pub fn is_tile(self, value: u32) -> bool {
+        self.tiles()
+            .take_while(|&tile| tile <= value)
+            .any(|tile| tile == value)
+    }
+
+    /// Tries to merge the tiles at the front of `tiles`, returning the new
+    /// value and how many tiles were consumed.
+    pub fn merge(self, tiles: &[u32]) -> Option<(u32, usize)> {
+        match (self, tiles) {
+            (MergeRule::Standard, [a, b, ..]) if a == b => Some((a * 2, 2)),
+            (MergeRule::Fibonacci, [a, b, ..]) if fibonacci_neighbours(*a, *b) => Some((a + b, 2)),
+            (MergeRule::Threes, [1, 2, ..] | [2, 1, ..]) => Some((3, 2)),
+            (MergeRule::Threes, [a, b, ..]) if a == b && *a >= 3 => Some((a * 2, 2)),
+            (MergeRule::Base3, [a, b, c, ..]) if a == b && b == c => Some((a * 3, 3)),
+            _ => None,
+        }
+    }
+
+    /// The tiles that merge into `value`, smallest first, as made by
+    /// [`MergeRule::merge`].
+    pub fn parts(self, value: u32) -> Vec<u32> {
+        match self {
+            MergeRule::Standard => vec![value / 2; 2],
+            MergeRule::Fibonacci => {
+                // The tile just below `value`, and the one below that
+                let below = self.tiles().take_while(|&tile| tile < value).last();
+                let below = below.unwrap_or(1);
+                vec![value - below, below]
+            }
+            MergeRule::Threes if value == 3 => vec![1, 2],
+            MergeRule::Threes => vec![value / 2; 2],
+            MergeRule::Base3 => vec![value / 3; 3],
+        }
+    }
+
+    /// Value of a newly spawned tile. `low` is true for the common spawn;
+    /// `coin` only matters for rules with more than two spawn values.
+    pub fn spawn(self, low: bool, coin: impl FnOnce() -> bool) -> u32 {
+        match (self, low) {
+            (MergeRule::Standard, true) => 2,
+            (MergeRule::Standard, false) => 4,
+            (MergeRule::Fibonacci, true) => 1,
+            (MergeRule::Fibonacci, false) => 2,
+            (MergeRule::Threes, true) => {
+                if coin() {
+                    1
+                } else {
+                    2
+                }
+            }
+            (MergeRule::Threes, false) => 3,
+            (MergeRule::Base3, true) => 3,
+            (MergeRule::Base3, false) => 9,
+        }
+    }
+
+    /// Maps a tile onto the standard game's value of the same rank, so that
+    /// every rule shares the powers-of-two palette.
+    pub fn palette_value(self, value: u32) -> u32 {
+        let rank = match self {
+            MergeRule::Standard => return value,
+            MergeRule::Fibonacci => fibonacci_rank(value),
+            // 3 looks like 2, 6 like 4 and so on
+            MergeRule::Threes => (value / 3).max(1).ilog2() + 1,
+            MergeRule::Base3 => value.max(1).ilog(3),
+        };
+        1u32.checked_shl(rank).unwrap_or(u32::MAX)
+    }
+}
+
+/// Position in 1, 2, 3, 5, 8, ..., starting from 1 for the value 1.
+fn fibonacci_rank(value: u32) -> u32 {
+    let (mut a, mut b, mut rank) = (1u32, 2u32, 1);
+    while a < value {
+        (a, b) = (b, a.saturating_add(b));
+        rank += 1;
+    }
+    rank
+}
+
+fn fibonacci_neighbours(a: u32, b: u32) -> bool {
+    let (low, high) = (a.min(b), a.max(b));
+    if low == 1 && high == 1 {
+        return true;
+    }
+    let (mut x, mut y) = (1u32, 2u32);
+    while x <= low {
+        if x == low && y == high {
+            return true;
+        }
+        (x, y) = (y, x.saturating_add(y));
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fibonacci_starts_with_two_ones() {
+        let rule = MergeRule::Fibonacci;
+        assert_eq!(rule.merge(&[1, 1]), Some((2, 2)));
+        assert_eq!(rule.merge(&[2, 1]), Some((3, 2)));
+        assert_eq!(rule.merge(&[3, 5, 8]), Some((8, 2)));
+        assert_eq!(rule.merge(&[2, 2]), None);
+        assert_eq!(rule.merge(&[1, 3]), None);
+    }
+
+    #[test]
+    fn threes_merges_one_and_two_then_equal_tiles() {
+        let rule = MergeRule::Threes;
+        assert_eq!(rule.merge(&[1, 2]), Some((3, 2)));
+        assert_eq!(rule.merge(&[2, 1]), Some((3, 2)));
+        assert_eq!(rule.merge(&[3, 3]), Some((6, 2)));
+        assert_eq!(rule.merge(&[1, 1]), None);
+        assert_eq!(rule.merge(&[2, 2]), None);
+        assert_eq!(rule.merge(&[3, 6]), None);
+    }
+
+    #[test]
+    fn base3_takes_three_tiles() {
+        let rule = MergeRule::Base3;
+        assert_eq!(rule.merge(&[3, 3, 3]), Some((9, 3)));
+        assert_eq!(rule.merge(&[3, 3, 3, 3]), Some((9, 3)));
+        assert_eq!(rule.merge(&[3, 3]), None);
+        assert_eq!(rule.merge(&[3, 3, 9]), None);
+    }
+
+    #[test]
+    fn tiles_stop_before_overflowing() {
+        for rule in MERGE_RULES {
+            let tiles: Vec<u32> = rule.tiles().collect();
+            assert!(tiles.windows(2).all(|pair| pair[0] < pair[1]), "{rule:?}");
+            assert!(rule.is_tile(*tiles.last().unwrap()));
+        }
+        assert_eq!(MergeRule::Standard.tiles().last(), Some(1 << 31));
+        assert_eq!(MergeRule::Base3.tiles().last(), Some(3u32.pow(20)));
+    }
+
+    #[test]
+    fn parts_merge_back_into_their_tile() {
+        for rule in MERGE_RULES {
+            // The smallest tiles only spawn: the 1 and 2 of Threes, and
+            // the first tile of the others
+            let spawned_only = if rule == MergeRule::Threes { 2 } else { 1 };
+            for value in rule.tiles().skip(spawned_only) {
+                let parts = rule.parts(value);
+                assert_eq!(
+                    rule.merge(&parts),
+                    Some((value, parts.len())),
+                    "{rule:?} {value}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,593 @@
+//! Preferences, kept in `settings.json` in the config directory rather than
+//! with the game, so they outlive any one save.
+
+use crate::focus::focusable;
+use crate::{Message, options};
+use clap::ValueEnum;
+use iced::keyboard;
+use iced::widget::{button, column, container, row, text};
+use iced::{Element, Length};
+use iced_2048::board::Shape;
+use iced_2048::config_file_path;
+use iced_2048::game::{Direction, Game, SHAPES, UNDO_POLICIES, UndoPolicy};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::time::Duration;
+
+const SETTINGS_FILE: &str = "settings.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    /// Whichever of the two the desktop prefers.
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+const THEMES: [Theme; 3] = [Theme::System, Theme::Light, Theme::Dark];
+
+impl Theme {
+    pub fn of(dark_mode: bool) -> Self {
+        if dark_mode { Theme::Dark } else { Theme::Light }
+    }
+
+    /// Whether the theme is dark, given the system's preference if it's
+    /// known and the manual choice to fall back on if not.
+    pub fn is_dark(self, system: Option<bool>, manual: Theme) -> bool {
+        match self {
+            Theme::System => system.unwrap_or(manual == Theme::Dark),
+            Theme::Light => false,
+            Theme::Dark => true,
+        }
+    }
+
+    fn label(self) -> String {
+        match self {
+            Theme::System => "System".to_string(),
+            Theme::Light => "Light".to_string(),
+            Theme::Dark => "Dark".to_string(),
+        }
+    }
+}
+
+/// Whether the desktop prefers dark colors: on Linux its XDG portal
+/// `color-scheme` setting, or failing that the GNOME or KDE theme. `None`
+/// if it has no preference or it can't be told.
+pub fn system_dark_mode() -> Option<bool> {
+    match dark_light::detect() {
+        dark_light::Mode::Dark => Some(true),
+        dark_light::Mode::Light => Some(false),
+        dark_light::Mode::Default => None,
+    }
+}
+
+/// How fast autoplay and exported replays go.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Speed {
+    Slow,
+    #[default]
+    Normal,
+    Fast,
+}
+
+const SPEEDS: [Speed; 3] = [Speed::Slow, Speed::Normal, Speed::Fast];
+
+impl Speed {
+    fn label(self) -> String {
+        match self {
+            Speed::Slow => "Slow".to_string(),
+            Speed::Normal => "Normal".to_string(),
+            Speed::Fast => "Fast".to_string(),
+        }
+    }
+
+    /// Time between autoplay's moves.
+    pub fn autoplay_interval(self) -> Duration {
+        Duration::from_millis(match self {
+            Speed::Slow => 400,
+            Speed::Normal => 150,
+            Speed::Fast => 50,
+        })
+    }
+
+    /// Time each move stays on screen in an exported replay.
+    pub fn replay_delay(self) -> Duration {
+        Duration::from_millis(match self {
+            Speed::Slow => 500,
+            Speed::Normal => 250,
+            Speed::Fast => 100,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Language {
+    #[default]
+    English,
+}
+
+const LANGUAGES: [Language; 1] = [Language::English];
+
+impl Language {
+    fn label(self) -> String {
+        match self {
+            Language::English => "English".to_string(),
+        }
+    }
+}
+
+/// Something a key can be bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyAction {
+    Move(Direction),
+    Undo,
+    Hint,
+    Autoplay,
+    ToggleTheme,
+    ReadBoard,
+}
+
+const KEY_ACTIONS: [KeyAction; 13] = [
+    KeyAction::Move(Direction::Up),
+    KeyAction::Move(Direction::Down),
+    KeyAction::Move(Direction::Left),
+    KeyAction::Move(Direction::Right),
+    KeyAction::Move(Direction::UpLeft),
+    KeyAction::Move(Direction::UpRight),
+    KeyAction::Move(Direction::DownLeft),
+    KeyAction::Move(Direction::DownRight),
+    KeyAction::Undo,
+    KeyAction::Hint,
+    KeyAction::Autoplay,
+    KeyAction::ToggleTheme,
+    KeyAction::ReadBoard,
+];
+
+impl KeyAction {
+    fn label(self) -> String {
+        match self {
+            KeyAction::Move(direction) => format!("Move {}", direction.label()),
+            KeyAction::Undo => "Undo".to_string(),
+            KeyAction::Hint => "Hint".to_string(),
+            KeyAction::Autoplay => "Autoplay".to_string(),
+            KeyAction::ToggleTheme => "Dark mode".to_string(),
+            KeyAction::ReadBoard => "Read board".to_string(),
+        }
+    }
+
+    /// Whether the action exists on a board of `shape`: the left and right
+    /// moves only on squares, and the diagonals only on hexes.
+    fn applies_to(self, shape: Shape) -> bool {
+        match self {
+            KeyAction::Move(direction) => shape.directions().contains(&direction),
+            _ => true,
+        }
+    }
+
+    /// Whether the two actions can't share a key, being on the same board.
+    fn clashes_with(self, other: KeyAction) -> bool {
+        self != other
+            && SHAPES
+                .iter()
+                .any(|&shape| self.applies_to(shape) && other.applies_to(shape))
+    }
+}
+
+/// Letter keys of the main game. The arrow keys always move too.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub up: char,
+    pub down: char,
+    pub left: char,
+    pub right: char,
+    pub up_left: char,
+    pub up_right: char,
+    pub down_left: char,
+    pub down_right: char,
+    pub undo: char,
+    pub hint: char,
+    pub autoplay: char,
+    pub toggle_theme: char,
+    pub read_board: char,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        // Hex boards reuse A and D for the lower diagonals
+        KeyBindings {
+            up: 'w',
+            down: 's',
+            left: 'a',
+            right: 'd',
+            up_left: 'q',
+            up_right: 'e',
+            down_left: 'a',
+            down_right: 'd',
+            undo: 'z',
+            hint: 'h',
+            autoplay: 'g',
+            toggle_theme: 'p',
+            read_board: 'r',
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn key(&self, action: KeyAction) -> char {
+        match action {
+            KeyAction::Move(Direction::Up) => self.up,
+            KeyAction::Move(Direction::Down) => self.down,
+            KeyAction::Move(Direction::Left) => self.left,
+            KeyAction::Move(Direction::Right) => self.right,
+            KeyAction::Move(Direction::UpLeft) => self.up_left,
+            KeyAction::Move(Direction::UpRight) => self.up_right,
+            KeyAction::Move(Direction::DownLeft) => self.down_left,
+            KeyAction::Move(Direction::DownRight) => self.down_right,
+            KeyAction::Undo => self.undo,
+            KeyAction::Hint => self.hint,
+            KeyAction::Autoplay => self.autoplay,
+            KeyAction::ToggleTheme => self.toggle_theme,
+            KeyAction::ReadBoard => self.read_board,
+        }
+    }
+
+    fn key_mut(&mut self, action: KeyAction) -> &mut char {
+        match action {
+            KeyAction::Move(Direction::Up) => &mut self.up,
+            KeyAction::Move(Direction::Down) => &mut self.down,
+            KeyAction::Move(Direction::Left) => &mut self.left,
+            KeyAction::Move(Direction::Right) => &mut self.right,
+            KeyAction::Move(Direction::UpLeft) => &mut self.up_left,
+            KeyAction::Move(Direction::UpRight) => &mut self.up_right,
+            KeyAction::Move(Direction::DownLeft) => &mut self.down_left,
+            KeyAction::Move(Direction::DownRight) => &mut self.down_right,
+            KeyAction::Undo => &mut self.undo,
+            KeyAction::Hint => &mut self.hint,
+            KeyAction::Autoplay => &mut self.autoplay,
+            KeyAction::ToggleTheme => &mut self.toggle_theme,
+            KeyAction::ReadBoard => &mut self.read_board,
+        }
+    }
+
+    /// Binds `key` to `action`. An action that had the key on the same
+    /// board swaps to the old key of `action`, unless that key would then
+    /// do two things.
+    fn bind(&mut self, action: KeyAction, key: char) -> Result<(), String> {
+        let mut bindings = self.clone();
+        let old = self.key(action);
+        for other in KEY_ACTIONS {
+            if action.clashes_with(other) && self.key(other) == key {
+                *bindings.key_mut(other) = old;
+            }
+        }
+        *bindings.key_mut(action) = key;
+        if let Some((first, second)) = bindings.clash() {
+            return Err(format!(
+                "{} would be both {} and {}",
+                key_label(bindings.key(first)),
+                first.label(),
+                second.label()
+            ));
+        }
+        *self = bindings;
+        Ok(())
+    }
+
+    /// Two actions on the same board with the same key, if there are any.
+    fn clash(&self) -> Option<(KeyAction, KeyAction)> {
+        KEY_ACTIONS.into_iter().find_map(|first| {
+            KEY_ACTIONS
+                .into_iter()
+                .find(|&second| first.clashes_with(second) && self.key(first) == self.key(second))
+                .map(|second| (first, second))
+        })
+    }
+
+    /// The action bound to a key on a board of `shape`.
+    pub fn action(&self, key: &keyboard::Key, shape: Shape) -> Option<KeyAction> {
+        let key = bindable(key)?;
+        KEY_ACTIONS
+            .into_iter()
+            .find(|&action| action.applies_to(shape) && self.key(action) == key)
+    }
+
+    /// The move keys of a shape, for the controls hint: `WASD` on squares.
+    pub fn move_keys(&self, shape: Shape) -> String {
+        let order: &[Direction] = match shape {
+            Shape::Square(_) => &[
+                Direction::Up,
+                Direction::Left,
+                Direction::Down,
+                Direction::Right,
+            ],
+            Shape::Hex(_) => &[
+                Direction::UpLeft,
+                Direction::Up,
+                Direction::UpRight,
+                Direction::DownLeft,
+                Direction::Down,
+                Direction::DownRight,
+            ],
+        };
+        let keys = order
+            .iter()
+            .map(|&direction| key_label(self.key(KeyAction::Move(direction))));
+        match shape {
+            Shape::Square(_) => keys.collect(),
+            Shape::Hex(_) => keys.collect::<Vec<_>>().join("/"),
+        }
+    }
+}
+
+/// A single printable character, lowercased, as keys are stored.
+fn bindable(key: &keyboard::Key) -> Option<char> {
+    let keyboard::Key::Character(text) = key.as_ref() else {
+        return None;
+    };
+    let mut chars = text.chars();
+    let key = chars.next()?.to_ascii_lowercase();
+    (chars.next().is_none() && key.is_ascii_graphic()).then_some(key)
+}
+
+pub fn key_label(key: char) -> String {
+    key.to_ascii_uppercase().to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub theme: Theme,
+    /// The last of light and dark picked by hand, for the system theme to
+    /// fall back on.
+    pub manual_theme: Theme,
+    /// Board of new games.
+    pub shape: Shape,
+    pub speed: Speed,
+    pub keys: KeyBindings,
+    /// Undo policy of new classic games.
+    pub undo_policy: UndoPolicy,
+    /// Not used yet: the game has no sounds.
+    pub sound: bool,
+    /// Not used yet: the game is only in English.
+    pub language: Language,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            theme: Theme::System,
+            manual_theme: Theme::Light,
+            shape: Shape::default(),
+            speed: Speed::default(),
+            keys: KeyBindings::default(),
+            undo_policy: UndoPolicy::default(),
+            sound: false,
+            language: Language::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Sets the theme, remembering it as the manual choice unless it
+    /// follows the system.
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+        if theme != Theme::System {
+            self.manual_theme = theme;
+        }
+    }
+
+    /// Whether `theme` is dark, asking the system only if it has to.
+    pub fn is_dark(&self, theme: Theme) -> bool {
+        let system = (theme == Theme::System).then(system_dark_mode).flatten();
+        theme.is_dark(system, self.manual_theme)
+    }
+
+    pub fn save(&self) {
+        if let Some(path) = config_file_path(SETTINGS_FILE)
+            && let Ok(json) = serde_json::to_string_pretty(self)
+        {
+            let _ = fs::write(path, json);
+        }
+    }
+
+    /// The saved settings. The first time, the theme and undo policy come
+    /// from the saved game, which is where they used to be kept.
+    pub fn load() -> Self {
+        config_file_path(SETTINGS_FILE)
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_else(|| match Game::load() {
+                Some(game) => Settings {
+                    theme: Theme::of(game.dark_mode),
+                    manual_theme: Theme::of(game.dark_mode),
+                    undo_policy: game.undo_policy,
+                    ..Settings::default()
+                },
+                None => Settings::default(),
+            })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Action {
+    SetTheme(Theme),
+    SetShape(Shape),
+    SetSpeed(Speed),
+    SetUndoPolicy(UndoPolicy),
+    SetSound(bool),
+    SetLanguage(Language),
+    /// Waits for the next key press to bind it to an action.
+    Rebind(KeyAction),
+    /// Back to the defaults, until applied.
+    Reset,
+    Apply,
+}
+
+/// The settings screen, editing a copy until it's applied.
+#[derive(Debug, Clone)]
+pub struct SettingsScreen {
+    pub draft: Settings,
+    rebinding: Option<KeyAction>,
+    /// Why the last key couldn't be bound.
+    status: Option<String>,
+}
+
+impl SettingsScreen {
+    pub fn new(settings: &Settings) -> Self {
+        SettingsScreen {
+            draft: settings.clone(),
+            rebinding: None,
+            status: None,
+        }
+    }
+
+    pub fn update(&mut self, action: Action) {
+        match action {
+            Action::SetTheme(theme) => self.draft.set_theme(theme),
+            Action::SetShape(shape) => self.draft.shape = shape,
+            Action::SetSpeed(speed) => self.draft.speed = speed,
+            Action::SetUndoPolicy(policy) => self.draft.undo_policy = policy,
+            Action::SetSound(sound) => self.draft.sound = sound,
+            Action::SetLanguage(language) => self.draft.language = language,
+            Action::Rebind(key_action) => {
+                self.rebinding = Some(key_action);
+                self.status = None;
+            }
+            Action::Reset => *self = SettingsScreen::new(&Settings::default()),
+            // Applying is up to the app
+            Action::Apply => self.rebinding = None,
+        }
+    }
+
+    /// Binds the key if one is being rebound, returning whether it was.
+    pub fn key_pressed(&mut self, key: &keyboard::Key) -> bool {
+        let Some(action) = self.rebinding else {
+            return false;
+        };
+        if key == &keyboard::Key::Named(keyboard::key::Named::Escape) {
+            self.rebinding = None;
+        } else if let Some(key) = bindable(key) {
+            self.status = self.draft.keys.bind(action, key).err();
+            self.rebinding = None;
+        }
+        true
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let title = text("Settings").size(40);
+        let themes = options(
+            "Theme",
+            &THEMES,
+            self.draft.theme,
+            Theme::label,
+            choice(Action::SetTheme),
+        );
+        let shapes = options(
+            "Board",
+            &SHAPES,
+            self.draft.shape,
+            Shape::label,
+            choice(Action::SetShape),
+        );
+        let speeds = options(
+            "Speed",
+            &SPEEDS,
+            self.draft.speed,
+            Speed::label,
+            choice(Action::SetSpeed),
+        );
+        let undo_policies = options(
+            "Undo",
+            &UNDO_POLICIES,
+            self.draft.undo_policy,
+            UndoPolicy::label,
+            choice(Action::SetUndoPolicy),
+        );
+        let sounds = options(
+            "Sound",
+            &[true, false],
+            self.draft.sound,
+            |sound| if sound { "On" } else { "Off" }.to_string(),
+            choice(Action::SetSound),
+        );
+        let languages = options(
+            "Language",
+            &LANGUAGES,
+            self.draft.language,
+            Language::label,
+            choice(Action::SetLanguage),
+        );
+
+        let key_columns = KEY_ACTIONS.chunks(5).map(|actions| {
+            column(actions.iter().map(|&action| {
+                let key = if self.rebinding == Some(action) {
+                    "Press a key…".to_string()
+                } else {
+                    key_label(self.draft.keys.key(action))
+                };
+                row![
+                    text(action.label()).size(16).width(150),
+                    focusable(
+                        button(text(key).size(14)).width(110).padding(8),
+                        Message::Settings(Action::Rebind(action)),
+                    ),
+                ]
+                .spacing(8)
+                .align_y(iced::Alignment::Center)
+                .into()
+            }))
+            .spacing(8)
+            .into()
+        });
+
+        let buttons = row![
+            focusable(
+                button("Apply").padding(12),
+                Message::Settings(Action::Apply)
+            ),
+            focusable(
+                button("Reset").padding(12),
+                Message::Settings(Action::Reset)
+            ),
+            focusable(button("Back").padding(12), Message::ShowModes),
+        ]
+        .spacing(12);
+
+        let content = column![
+            title,
+            themes,
+            shapes,
+            speeds,
+            undo_policies,
+            sounds,
+            languages,
+            text("Sounds and other languages are still to come").size(14),
+            text("Keys").size(22),
+            row(key_columns).spacing(32),
+            text(self.status.as_deref().unwrap_or(
+                "Click a key to change it, then press the new one; the arrow keys always move"
+            ))
+            .size(14),
+            buttons,
+        ]
+        .spacing(16)
+        .align_x(iced::Alignment::Center);
+
+        container(content)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .center(Length::Fill)
+            .padding(20)
+            .into()
+    }
+}
+
+fn choice<T>(action: fn(T) -> Action) -> impl Fn(T) -> Message {
+    move |value| Message::Settings(action(value))
+}
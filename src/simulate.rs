@@ -0,0 +1,180 @@
+//! Headless games between strategies.
+//!
+//! In a match every strategy plays the same seeds, so they all face the same
+//! spawn sequence and differences in score come from their moves.
+
+use crate::game::{Game, Variant};
+use crate::montecarlo::MonteCarlo;
+use crate::ntuple::NTupleNetwork;
+use crate::strategy::{Greedy, Strategy};
+use std::fmt::Write;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Names of the strategies [`strategy`] can build.
+pub const STRATEGY_NAMES: [&str; 3] = ["greedy", "ntuple", "montecarlo"];
+
+/// Settings for the strategies built by [`strategy`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StrategyOptions {
+    /// Monte Carlo playouts per move.
+    pub rollouts: u32,
+    /// Caps the Monte Carlo search per move, however many playouts it got to.
+    pub time_budget: Option<Duration>,
+    /// Monte Carlo threads; all cores when unset.
+    pub threads: Option<usize>,
+    /// N-tuple weights, instead of `ntuple.bin` in the data directory.
+    pub weights: Option<PathBuf>,
+}
+
+impl Default for StrategyOptions {
+    fn default() -> Self {
+        StrategyOptions {
+            rollouts: 100,
+            time_budget: None,
+            threads: None,
+            weights: None,
+        }
+    }
+}
+
+/// The strategy called `name`, one of [`STRATEGY_NAMES`].
+pub fn strategy(name: &str, options: &StrategyOptions) -> Result<Box<dyn Strategy>, String> {
+    match name {
+        "greedy" => Ok(Box::new(Greedy)),
+        "ntuple" => {
+            let path = options
+                .weights
+                .clone()
+                .or_else(|| crate::data_file_path("ntuple.bin"))
+                .ok_or("No data directory found; pass --weights")?;
+            NTupleNetwork::load(&path)
+                .map(|network| Box::new(network) as Box<dyn Strategy>)
+                .map_err(|error| format!("Couldn't load {}: {error}", path.display()))
+        }
+        "montecarlo" => {
+            let mut monte_carlo = MonteCarlo::new(options.rollouts, options.time_budget, 0);
+            if let Some(threads) = options.threads {
+                monte_carlo.threads = threads;
+            }
+            Ok(Box::new(monte_carlo))
+        }
+        other => Err(format!("unknown strategy {other}")),
+    }
+}
+
+/// How one game went.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Outcome {
+    pub seed: u64,
+    pub score: u32,
+    pub moves: u32,
+    pub max_tile: u32,
+    pub won: bool,
+}
+
+/// Plays a seeded game of `variant` to the end.
+pub fn play(strategy: &mut dyn Strategy, variant: Variant, seed: u64) -> Outcome {
+    let mut game = Game::new_seeded(seed, variant, false);
+    while let Some(direction) = strategy.choose(&game) {
+        if !game.move_tiles(direction) {
+            // A strategy that keeps choosing a move that does nothing would
+            // never finish
+            break;
+        }
+    }
+    Outcome {
+        seed,
+        score: game.score,
+        moves: game.moves,
+        max_tile: game.max_tile(),
+        won: game.won,
+    }
+}
+
+/// One strategy's results in a match.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub name: String,
+    pub outcomes: Vec<Outcome>,
+    /// Seeds on which this strategy scored more than every other one.
+    pub wins: u32,
+}
+
+impl Summary {
+    pub fn mean_score(&self) -> f64 {
+        let total: u64 = self.outcomes.iter().map(|o| u64::from(o.score)).sum();
+        total as f64 / self.outcomes.len().max(1) as f64
+    }
+
+    pub fn best_score(&self) -> u32 {
+        self.outcomes.iter().map(|o| o.score).max().unwrap_or(0)
+    }
+
+    /// Share of games that reached the winning tile.
+    pub fn win_rate(&self) -> f64 {
+        let won = self.outcomes.iter().filter(|o| o.won).count();
+        won as f64 / self.outcomes.len().max(1) as f64
+    }
+}
+
+/// The results of a match as a text table, one strategy per line.
+pub fn table(summaries: &[Summary]) -> String {
+    let mut table = format!(
+        "{:<28} {:>10} {:>10} {:>8} {:>6}\n",
+        "Strategy", "Mean", "Best", "2048", "Wins"
+    );
+    for summary in summaries {
+        let _ = writeln!(
+            table,
+            "{:<28} {:>10.0} {:>10} {:>7.1}% {:>6}",
+            summary.name,
+            summary.mean_score(),
+            summary.best_score(),
+            100.0 * summary.win_rate(),
+            summary.wins
+        );
+    }
+    table
+}
+
+/// Plays every seed with every strategy. `progress` is called after each
+/// seed with the number of seeds played so far.
+pub fn head_to_head(
+    strategies: &mut [Box<dyn Strategy>],
+    variant: Variant,
+    seeds: Range<u64>,
+    mut progress: impl FnMut(u64),
+) -> Vec<Summary> {
+    let mut summaries: Vec<Summary> = strategies
+        .iter()
+        .map(|strategy| Summary {
+            name: strategy.name(),
+            outcomes: Vec::new(),
+            wins: 0,
+        })
+        .collect();
+    for (played, seed) in seeds.enumerate() {
+        let scores: Vec<u32> = strategies
+            .iter_mut()
+            .zip(&mut summaries)
+            .map(|(strategy, summary)| {
+                let outcome = play(strategy.as_mut(), variant, seed);
+                summary.outcomes.push(outcome);
+                outcome.score
+            })
+            .collect();
+        for (i, summary) in summaries.iter_mut().enumerate() {
+            let beats_all = scores
+                .iter()
+                .enumerate()
+                .all(|(j, &score)| i == j || scores[i] > score);
+            if beats_all && scores.len() > 1 {
+                summary.wins += 1;
+            }
+        }
+        progress(played as u64 + 1);
+    }
+    summaries
+}
@@ -0,0 +1,131 @@
+//! A read-only endpoint for watching a game from a browser.
+//!
+//! The server answers three requests on one port:
+//!
+//! - `GET /` serves a small HTML page that renders the board and the moves
+//!   played while it's open.
+//! - `GET /state` returns the latest [`PlayerState`] as JSON, or `null`
+//!   before the first one is published.
+//! - `GET /ws` upgrades to a WebSocket that receives the latest state on
+//!   connect, then a JSON text message after every change.
+//!
+//! Nothing sent by spectators affects the game.
+
+use crate::http::{read_request, respond};
+use crate::protocol::PlayerState;
+use std::io::{self, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tungstenite::handshake::derive_accept_key;
+use tungstenite::protocol::Role;
+use tungstenite::{Message, WebSocket};
+
+/// Address used when spectating is enabled without one. Only reachable
+/// from this machine.
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:8048";
+
+const PAGE: &str = include_str!("spectate.html");
+
+#[derive(Default)]
+struct Shared {
+    /// JSON of the last published state.
+    latest: Mutex<Option<String>>,
+    /// One channel per connected WebSocket.
+    clients: Mutex<Vec<Sender<String>>>,
+}
+
+/// A running spectator endpoint. It keeps serving until the process exits.
+pub struct Spectators {
+    address: SocketAddr,
+    shared: Arc<Shared>,
+}
+
+impl Spectators {
+    /// Binds to `address` and starts serving on a background thread.
+    pub fn start(address: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        let address = listener.local_addr()?;
+        let shared = Arc::new(Shared::default());
+        let accepting = Arc::clone(&shared);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let shared = Arc::clone(&accepting);
+                thread::spawn(move || {
+                    let _ = handle_connection(stream, &shared);
+                });
+            }
+        });
+        Ok(Spectators { address, shared })
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Pushes `state` to every spectator, unless it's unchanged.
+    pub fn publish(&self, state: &PlayerState) {
+        let Ok(json) = serde_json::to_string(state) else {
+            return;
+        };
+        let mut latest = self.shared.latest.lock().unwrap();
+        if latest.as_ref() == Some(&json) {
+            return;
+        }
+        self.shared
+            .clients
+            .lock()
+            .unwrap()
+            .retain(|client| client.send(json.clone()).is_ok());
+        *latest = Some(json);
+    }
+}
+
+fn handle_connection(stream: TcpStream, shared: &Shared) -> io::Result<()> {
+    let request = read_request(&stream)?;
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/ws") => match request.header("sec-websocket-key") {
+            Some(key) => stream_states(stream, key, shared),
+            None => respond(
+                &stream,
+                "400 Bad Request",
+                "text/plain",
+                "Expected a WebSocket",
+            ),
+        },
+        ("GET", "/") => respond(&stream, "200 OK", "text/html; charset=utf-8", PAGE),
+        ("GET", "/state") => {
+            let latest = shared.latest.lock().unwrap().clone();
+            let body = latest.unwrap_or_else(|| "null".to_string());
+            respond(&stream, "200 OK", "application/json", &body)
+        }
+        _ => respond(&stream, "404 Not Found", "text/plain", "Not found"),
+    }
+}
+
+/// Completes the WebSocket handshake, then forwards every published state
+/// until the spectator goes away.
+fn stream_states(mut stream: TcpStream, key: &str, shared: &Shared) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        derive_accept_key(key.as_bytes())
+    )?;
+
+    let (sender, receiver): (Sender<String>, Receiver<String>) = mpsc::channel();
+    // Register under the lock on `latest` so no publish slips in between
+    let latest = {
+        let latest = shared.latest.lock().unwrap();
+        shared.clients.lock().unwrap().push(sender);
+        latest.clone()
+    };
+
+    let mut socket = WebSocket::from_raw_socket(stream, Role::Server, None);
+    for json in latest.into_iter().chain(receiver) {
+        if socket.send(Message::text(json)).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,52 @@
+//! Move-choosing engines, used for hints, autoplay and simulations.
+//!
+//! Besides [`Greedy`] there are the [`crate::ntuple`] network and the
+//! [`crate::montecarlo`] search; [`crate::simulate`] plays them against each
+//! other.
+
+use crate::game::{Cell, Direction, Game};
+
+pub trait Strategy {
+    fn name(&self) -> String;
+
+    /// The move to play next, or `None` if there's no move or this strategy
+    /// can't play the game's variant.
+    fn choose(&mut self, game: &Game) -> Option<Direction>;
+}
+
+/// Takes the move that scores most, preferring the one leaving the most
+/// empty cells on a tie. Plays every variant.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Greedy;
+
+impl Strategy for Greedy {
+    fn name(&self) -> String {
+        "Greedy".to_string()
+    }
+
+    fn choose(&mut self, game: &Game) -> Option<Direction> {
+        if game.game_over {
+            return None;
+        }
+        game.board
+            .shape()
+            .directions()
+            .iter()
+            .filter_map(|&direction| {
+                let (board, merges) = game.slide(direction);
+                if board == game.board {
+                    return None;
+                }
+                let empty = board
+                    .cells()
+                    .iter()
+                    .filter(|&&cell| cell == Cell::Empty)
+                    .count();
+                Some(((merges.iter().sum::<u32>(), empty), direction))
+            })
+            // `max_by_key` keeps the last maximum; reversing keeps the first
+            .rev()
+            .max_by_key(|&(key, _)| key)
+            .map(|(_, direction)| direction)
+    }
+}
@@ -0,0 +1,131 @@
+use crate::focus::focusable;
+use crate::{Message, key_direction, view_board};
+use iced::keyboard;
+use iced::widget::{button, column, container, row, text};
+use iced::{Element, Length};
+use iced_2048::board::Shape;
+use iced_2048::game::{Game, Variant};
+use rand::Rng;
+
+const PLAYER_NAMES: [&str; 2] = ["Player 1 (WASD)", "Player 2 (Arrows)"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Winner(usize),
+    Draw,
+}
+
+/// A local two-player match. Both boards share a seed, so both players face
+/// the same spawn sequence.
+#[derive(Debug, Clone)]
+pub struct Versus {
+    players: [Game; 2],
+    outcome: Option<Outcome>,
+}
+
+impl Versus {
+    pub fn new(variant: Variant, dark_mode: bool) -> Self {
+        // Arrow keys only cover four directions, so matches use a square board
+        let variant = Variant {
+            shape: Shape::default(),
+            ..variant
+        };
+        let seed = rand::rng().random();
+        Versus {
+            players: [0, 1].map(|_| Game::new_seeded(seed, variant, dark_mode)),
+            outcome: None,
+        }
+    }
+
+    pub fn set_dark_mode(&mut self, dark_mode: bool) {
+        for game in &mut self.players {
+            game.dark_mode = dark_mode;
+        }
+    }
+
+    /// Routes a key to its player: letters belong to player 1 and the
+    /// arrow keys to player 2.
+    pub fn key_pressed(&mut self, key: &keyboard::Key) {
+        if self.outcome.is_some() {
+            return;
+        }
+        let player = match key {
+            keyboard::Key::Character(_) => 0,
+            keyboard::Key::Named(_) => 1,
+            keyboard::Key::Unidentified => return,
+        };
+        let game = &mut self.players[player];
+        if let Some(direction) = key_direction(game.board.shape(), key)
+            && !game.game_over
+        {
+            game.move_tiles(direction);
+        }
+        self.outcome = self.decide();
+    }
+
+    /// The first player to reach the target tile wins. Otherwise, once both
+    /// boards are stuck, the higher score wins.
+    fn decide(&self) -> Option<Outcome> {
+        if let Some(player) = self.players.iter().position(|game| game.won) {
+            return Some(Outcome::Winner(player));
+        }
+        if !self.players.iter().all(|game| game.game_over) {
+            return None;
+        }
+        let [first, second] = &self.players;
+        Some(match first.score.cmp(&second.score) {
+            std::cmp::Ordering::Greater => Outcome::Winner(0),
+            std::cmp::Ordering::Less => Outcome::Winner(1),
+            std::cmp::Ordering::Equal => Outcome::Draw,
+        })
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let title = text("Versus").size(50);
+
+        let boards = row(self.players.iter().zip(PLAYER_NAMES).map(|(game, name)| {
+            let status = if game.won {
+                format!("{name} • Score: {} • Target reached!", game.score)
+            } else if game.game_over {
+                format!("{name} • Score: {} • Stuck", game.score)
+            } else {
+                format!("{name} • Score: {}", game.score)
+            };
+            column![
+                text(status).size(20),
+                view_board(&game.board, game.variant.rule, game.dark_mode)
+            ]
+            .spacing(12)
+            .align_x(iced::Alignment::Center)
+            .into()
+        }))
+        .spacing(40);
+
+        let target = self.players[0].variant.rule.win_target();
+        let status_text = match self.outcome {
+            Some(Outcome::Winner(player)) => text(format!("{} wins!", PLAYER_NAMES[player])),
+            Some(Outcome::Draw) => text("It's a draw!"),
+            None => text(format!(
+                "First to {target} wins, or the higher score once both boards are stuck"
+            )),
+        }
+        .size(18);
+
+        let button_row = row![
+            focusable(button("Rematch").padding(12), Message::StartVersus),
+            focusable(button("Back").padding(12), Message::ShowModes),
+        ]
+        .spacing(12);
+
+        let content = column![title, boards, status_text, button_row]
+            .spacing(20)
+            .align_x(iced::Alignment::Center);
+
+        container(content)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .center(Length::Fill)
+            .padding(20)
+            .into()
+    }
+}
@@ -0,0 +1,161 @@
+use iced_2048::api::{self, Command};
+use iced_2048::game::Direction;
+use iced_2048::protocol::PlayerState;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+/// One test at a time, as running out of file descriptors affects the whole
+/// process.
+static SERIAL: Mutex<()> = Mutex::new(());
+
+/// Starts the API with a stand-in game that counts the moves it's sent.
+fn start_api() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel::<api::Request>();
+    thread::spawn(move || api::serve(listener, sender));
+    thread::spawn(move || {
+        let mut state = sample_state();
+        while let Some(request) = receiver.blocking_recv() {
+            let response = match &request.command {
+                Command::Move(name) if Direction::from_name(name).is_none() => {
+                    Err(format!("unknown direction {name}"))
+                }
+                Command::Move(_) => {
+                    state.moves += 1;
+                    Ok(state.clone())
+                }
+                Command::State | Command::Undo => Ok(state.clone()),
+                Command::NewGame => {
+                    state = sample_state();
+                    Ok(state.clone())
+                }
+            };
+            request.respond(response);
+        }
+    });
+    address
+}
+
+fn sample_state() -> PlayerState {
+    PlayerState {
+        score: 0,
+        moves: 0,
+        board: vec![vec![2, 0, 0, 0], vec![0; 4], vec![0; 4], vec![0, 0, 0, 2]],
+        game_over: false,
+        won: false,
+        last_move: None,
+    }
+}
+
+/// Connects and sends a request, without waiting for the answer.
+fn send(address: SocketAddr, method: &str, path: &str, body: &str) -> TcpStream {
+    let mut stream = TcpStream::connect(address).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    write!(
+        stream,
+        "{method} {path} HTTP/1.1\r\nContent-Length: {}\r\n\r\n{body}",
+        body.len()
+    )
+    .unwrap();
+    stream
+}
+
+/// The status code and body of the answer on `stream`.
+fn receive(mut stream: TcpStream) -> (u16, serde_json::Value) {
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+    (status, serde_json::from_str(body).unwrap())
+}
+
+fn request(address: SocketAddr, method: &str, path: &str, body: &str) -> (u16, serde_json::Value) {
+    receive(send(address, method, path, body))
+}
+
+#[test]
+fn answers_commands_with_the_game_state() {
+    let _serial = SERIAL
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let address = start_api();
+
+    let (status, state) = request(address, "GET", "/state", "");
+    assert_eq!(status, 200);
+    assert_eq!(state["moves"], 0);
+
+    let (status, state) = request(address, "POST", "/move", r#"{"direction":"left"}"#);
+    assert_eq!(status, 200);
+    assert_eq!(state["moves"], 1);
+
+    let (status, state) = request(address, "POST", "/new", "");
+    assert_eq!(status, 200);
+    assert_eq!(state["moves"], 0);
+}
+
+#[test]
+fn rejects_bad_requests() {
+    let _serial = SERIAL
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let address = start_api();
+
+    let (status, answer) = request(address, "POST", "/move", r#"{"direction":"sideways"}"#);
+    assert_eq!(status, 400);
+    assert_eq!(answer["error"], "unknown direction sideways");
+
+    let (status, _) = request(address, "POST", "/move", "not json");
+    assert_eq!(status, 400);
+    let (status, _) = request(address, "GET", "/move", "");
+    assert_eq!(status, 405);
+    let (status, _) = request(address, "GET", "/nowhere", "");
+    assert_eq!(status, 404);
+}
+
+#[cfg(unix)]
+#[test]
+fn keeps_serving_after_running_out_of_file_descriptors() {
+    let _serial = SERIAL
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let address = start_api();
+
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    assert_eq!(
+        unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) },
+        0
+    );
+    // Low enough to run out of quickly
+    let low = libc::rlimit {
+        rlim_cur: limit.rlim_cur.min(256),
+        ..limit
+    };
+    assert_eq!(unsafe { libc::setrlimit(libc::RLIMIT_NOFILE, &low) }, 0);
+
+    // Take every descriptor but one, which the client uses up, leaving the
+    // server none to accept it with
+    let mut files = Vec::new();
+    while let Ok(file) = std::fs::File::open("/dev/null") {
+        files.push(file);
+    }
+    assert!(files.pop().is_some());
+    let client = send(address, "GET", "/state", "");
+    // Long enough for a few accepts to fail
+    thread::sleep(Duration::from_millis(300));
+
+    drop(files);
+    assert_eq!(unsafe { libc::setrlimit(libc::RLIMIT_NOFILE, &limit) }, 0);
+    let (status, _) = receive(client);
+    assert_eq!(status, 200);
+    let (status, _) = request(address, "GET", "/state", "");
+    assert_eq!(status, 200);
+}
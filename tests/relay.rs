@@ -0,0 +1,204 @@
+use iced_2048::game::Direction;
+use iced_2048::protocol::{
+    ClientMessage, PROTOCOL_VERSION, PlayerState, ServerMessage, decode, encode,
+};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+fn start_relay() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    thread::spawn(move || iced_2048::relay::serve(listener));
+    address
+}
+
+struct Client {
+    stream: TcpStream,
+    reader: BufReader<TcpStream>,
+}
+
+impl Client {
+    fn connect(address: SocketAddr) -> Self {
+        let stream = TcpStream::connect(address).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let reader = BufReader::new(stream.try_clone().unwrap());
+        Client { stream, reader }
+    }
+
+    fn join(address: SocketAddr, name: &str) -> Self {
+        let mut client = Client::connect(address);
+        client.send(&ClientMessage::Join {
+            name: name.to_string(),
+            version: PROTOCOL_VERSION,
+        });
+        assert_eq!(client.receive(), ServerMessage::Welcome);
+        client
+    }
+
+    fn send(&mut self, message: &ClientMessage) {
+        self.send_line(&encode(message));
+    }
+
+    fn send_line(&mut self, line: &str) {
+        self.stream.write_all(line.as_bytes()).unwrap();
+    }
+
+    fn receive(&mut self) -> ServerMessage {
+        let mut line = String::new();
+        self.reader.read_line(&mut line).unwrap();
+        decode(&line).unwrap()
+    }
+}
+
+fn start_seed(message: ServerMessage, expected_opponent: &str) -> u64 {
+    match message {
+        ServerMessage::Start { seed, opponent } => {
+            assert_eq!(opponent, expected_opponent);
+            seed
+        }
+        other => panic!("expected start, got {other:?}"),
+    }
+}
+
+fn sample_state() -> PlayerState {
+    PlayerState {
+        score: 12,
+        moves: 3,
+        board: vec![
+            vec![4, 0, 0, 0],
+            vec![0, 8, 0, 0],
+            vec![0, 0, -1, 0],
+            vec![0, 0, 0, 2],
+        ],
+        game_over: false,
+        won: false,
+        last_move: None,
+    }
+}
+
+#[test]
+fn messages_round_trip_as_single_lines() {
+    let message = ClientMessage::Update(sample_state());
+    let line = encode(&message);
+    assert!(line.ends_with('\n'));
+    assert_eq!(line.matches('\n').count(), 1);
+    assert_eq!(decode::<ClientMessage>(&line).unwrap(), message);
+
+    let start = decode::<ServerMessage>(r#"{"type":"start","seed":7,"opponent":"bob"}"#).unwrap();
+    assert_eq!(
+        start,
+        ServerMessage::Start {
+            seed: 7,
+            opponent: "bob".to_string()
+        }
+    );
+}
+
+#[test]
+fn updates_carry_the_last_move() {
+    let state = PlayerState {
+        last_move: Some(Direction::UpLeft),
+        ..sample_state()
+    };
+    let line = encode(&ClientMessage::Update(state.clone()));
+    assert!(line.contains(r#""last_move":"up_left""#));
+    assert_eq!(
+        decode::<ClientMessage>(&line).unwrap(),
+        ClientMessage::Update(state)
+    );
+
+    // Clients from before the field send none
+    let line = r#"{"type":"update","score":0,"moves":0,"board":[],"game_over":false,"won":false}"#;
+    let ClientMessage::Update(state) = decode(line).unwrap() else {
+        panic!("expected an update");
+    };
+    assert_eq!(state.last_move, None);
+}
+
+#[test]
+fn pairs_clients_with_a_shared_seed() {
+    let address = start_relay();
+    let mut alice = Client::join(address, "alice");
+    let mut bob = Client::join(address, "bob");
+
+    let alice_seed = start_seed(alice.receive(), "bob");
+    let bob_seed = start_seed(bob.receive(), "alice");
+    assert_eq!(alice_seed, bob_seed);
+}
+
+#[test]
+fn relays_updates_to_the_opponent() {
+    let address = start_relay();
+    let mut alice = Client::join(address, "alice");
+    let mut bob = Client::join(address, "bob");
+    alice.receive();
+    bob.receive();
+
+    alice.send(&ClientMessage::Update(sample_state()));
+    assert_eq!(bob.receive(), ServerMessage::Opponent(sample_state()));
+
+    let finished = PlayerState {
+        game_over: true,
+        ..sample_state()
+    };
+    bob.send(&ClientMessage::Update(finished.clone()));
+    assert_eq!(alice.receive(), ServerMessage::Opponent(finished));
+}
+
+#[test]
+fn notifies_when_the_opponent_leaves() {
+    let address = start_relay();
+    let mut alice = Client::join(address, "alice");
+    let mut bob = Client::join(address, "bob");
+    alice.receive();
+    bob.receive();
+
+    drop(bob);
+    assert_eq!(alice.receive(), ServerMessage::OpponentLeft);
+}
+
+#[test]
+fn separate_pairs_do_not_interfere() {
+    let address = start_relay();
+    let mut first = [Client::join(address, "a1"), Client::join(address, "a2")];
+    let mut second = [Client::join(address, "b1"), Client::join(address, "b2")];
+    for client in first.iter_mut().chain(second.iter_mut()) {
+        client.receive();
+    }
+
+    second[0].send(&ClientMessage::Update(sample_state()));
+    assert_eq!(second[1].receive(), ServerMessage::Opponent(sample_state()));
+
+    let other = PlayerState {
+        score: 99,
+        ..sample_state()
+    };
+    first[1].send(&ClientMessage::Update(other.clone()));
+    assert_eq!(first[0].receive(), ServerMessage::Opponent(other));
+}
+
+#[test]
+fn rejects_malformed_lines_and_wrong_versions() {
+    let address = start_relay();
+    let mut client = Client::connect(address);
+
+    client.send_line("not json\n");
+    assert!(matches!(client.receive(), ServerMessage::Error { .. }));
+
+    client.send(&ClientMessage::Join {
+        name: "old".to_string(),
+        version: PROTOCOL_VERSION + 1,
+    });
+    assert!(matches!(client.receive(), ServerMessage::Error { .. }));
+
+    // The connection stays usable after an error
+    client.send(&ClientMessage::Join {
+        name: "fixed".to_string(),
+        version: PROTOCOL_VERSION,
+    });
+    assert_eq!(client.receive(), ServerMessage::Welcome);
+}